@@ -1,27 +1,11 @@
-use std::borrow::Cow;
 use std::env::args;
 use std::io::BufRead;
 use std::process;
 
-use conllx::Token;
-use conllx_utils::{layer_callback, open_reader, or_exit, LayerCallback};
+use conllx_utils::{feature_callback, layer_callback, open_reader, or_exit, LayerCallback};
 use failure::{ensure, Error};
 use getopts::Options;
 
-fn feature_callback(feature: impl Into<String>) -> Box<dyn Fn(&Token) -> Option<Cow<'_, str>>> {
-    let feature = feature.into();
-
-    Box::new(move |token| match token.features() {
-        Some(features) => features
-            .as_map()
-            .get(&feature)
-            .map(Option::as_ref)
-            .unwrap_or(None)
-            .map(|s| Cow::Borrowed(s.as_str())),
-        None => None,
-    })
-}
-
 fn print_usage(program: &str, opts: Options) {
     let brief = format!("Usage: {} [options] FILE...", program);
     print!("{}", opts.usage(&brief));
@@ -104,7 +88,7 @@ fn compare_sentences(
     let mut total = 0;
     let mut correct = 0;
 
-    for (sent1, sent2) in reader1.into_iter().zip(reader2.into_iter()) {
+    for (sent1, sent2) in reader1.into_iter().zip(reader2) {
         let (sent1, sent2) = (sent1?, sent2?);
 
         ensure!(
@@ -1,10 +1,17 @@
+use std::collections::HashMap;
 use std::env::args;
-use std::io::BufWriter;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter};
 
 use conllx::{Sentence, WriteSentence};
-use conllx_utils::{or_exit, simplify_unicode, Normalization};
+use conllx_utils::{
+    fullwidth_to_ascii, is_invisible, normalize, normalize_whitespace, or_exit, remove_diacritics,
+    simplify_unicode_punct, Conversion, Normalization,
+};
+use failure::{ensure, format_err, Error};
 use getopts::Options;
 use stdinout::{Input, OrExit, Output};
+use unicode_normalization::char::canonical_combining_class;
 
 fn print_usage(program: &str, opts: Options) {
     let brief = format!("Usage: {} [options] [INPUT_FILE] [OUTPUT_FILE]", program);
@@ -25,17 +32,273 @@ where
     }
 }
 
+/// Read a character mapping table.
+///
+/// Each line consists of a character and its replacement, separated by a
+/// tab. The character can also be given as a code point, such as `U+00AB`.
+/// The replacement can be empty to remove the character. Empty lines and
+/// lines starting with `#` are ignored.
+fn read_mapping(read: impl BufRead) -> Result<HashMap<char, String>, Error> {
+    let mut mapping = HashMap::new();
+
+    for (line_idx, line) in read.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut parts = line.splitn(2, '\t');
+        let from = parts.next().unwrap();
+        let to = parts.next().ok_or_else(|| {
+            format_err!(
+                "Line {}: expected a character and its replacement",
+                line_idx + 1
+            )
+        })?;
+
+        let c = parse_char(from)
+            .ok_or_else(|| format_err!("Line {}: not a character: {}", line_idx + 1, from))?;
+        ensure!(
+            mapping.insert(c, to.to_owned()).is_none(),
+            "Line {}: duplicate mapping for: {}",
+            line_idx + 1,
+            from
+        );
+    }
+
+    Ok(mapping)
+}
+
+/// Parse a character or a code point, such as `U+00AB`.
+fn parse_char(s: &str) -> Option<char> {
+    if let Some(code_point) = s.strip_prefix("U+") {
+        return u32::from_str_radix(code_point, 16)
+            .ok()
+            .and_then(std::char::from_u32);
+    }
+
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
+}
+
+/// Counts of replacements of characters, or of characters followed by
+/// combining characters. Removals have an empty replacement.
+type Changes = HashMap<(String, String), usize>;
+
+fn record_change(changes: &mut Changes, original: impl Into<String>, replacement: String) {
+    *changes.entry((original.into(), replacement)).or_insert(0) += 1;
+}
+
+/// Split a string into segments that start with a character with
+/// canonical combining class 0, followed by combining characters.
+fn segments(s: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+
+    let mut start = 0;
+    for (idx, c) in s.char_indices() {
+        if idx != 0 && canonical_combining_class(c) == 0 {
+            segments.push(&s[start..idx]);
+            start = idx;
+        }
+    }
+
+    if start != s.len() {
+        segments.push(&s[start..]);
+    }
+
+    segments
+}
+
+/// Record the changes of a transformation to the segments of a string.
+///
+/// This is used to report transformations that are applied to the
+/// string as a whole, such as Unicode normalization.
+fn record_segment_changes(s: &str, changes: &mut Changes, transform: impl Fn(&str) -> String) {
+    for segment in segments(s) {
+        let transformed = transform(segment);
+        if transformed != segment {
+            record_change(changes, segment, transformed);
+        }
+    }
+}
+
+/// Record the changes of `normalize_whitespace`.
+fn record_whitespace_changes(s: &str, changes: &mut Changes) {
+    let mut seen_word = false;
+    let mut run = Vec::new();
+
+    for c in s.chars() {
+        if c.is_whitespace() {
+            run.push(c);
+            continue;
+        }
+
+        // A run between words is replaced by a single space.
+        for (idx, &ws) in run.iter().enumerate() {
+            if seen_word && idx == 0 {
+                if ws != ' ' {
+                    record_change(changes, ws, " ".to_owned());
+                }
+            } else {
+                record_change(changes, ws, String::new());
+            }
+        }
+
+        run.clear();
+        seen_word = true;
+    }
+
+    // Trailing whitespace is removed.
+    for ws in run {
+        record_change(changes, ws, String::new());
+    }
+}
+
+/// Cleanup steps, applied in the order of the fields.
+///
+/// Characters are mapped using the user-supplied mapping first, falling
+/// back to the fullwidth and punctuation mappings.
+struct Cleanup {
+    invisible: bool,
+    norm: Normalization,
+    mapping: HashMap<char, String>,
+    fullwidth: bool,
+    punct: bool,
+    diacritics: bool,
+    whitespace: bool,
+    lowercase: bool,
+}
+
+impl Cleanup {
+    /// Clean up a string, counting character replacements and removals.
+    fn clean(&self, s: &str, changes: &mut Changes) -> String {
+        let mut clean = String::with_capacity(s.len());
+        for c in s.chars() {
+            if self.invisible && is_invisible(c) {
+                record_change(changes, c, String::new());
+            } else {
+                clean.push(c);
+            }
+        }
+
+        if !matches!(self.norm, Normalization::None) {
+            record_segment_changes(&clean, changes, |segment| normalize(segment, self.norm));
+        }
+        let normalized = normalize(&clean, self.norm);
+
+        clean.clear();
+        for c in normalized.chars() {
+            match self.map_char(c) {
+                Some(replacement) => {
+                    clean.push_str(&replacement);
+                    record_change(changes, c, replacement);
+                }
+                None => clean.push(c),
+            }
+        }
+
+        if self.diacritics {
+            record_segment_changes(&clean, changes, remove_diacritics);
+            clean = remove_diacritics(&clean);
+        }
+
+        if self.whitespace {
+            record_whitespace_changes(&clean, changes);
+            clean = normalize_whitespace(&clean);
+        }
+
+        if self.lowercase {
+            record_segment_changes(&clean, changes, str::to_lowercase);
+            clean = clean.to_lowercase();
+        }
+
+        clean
+    }
+
+    /// Get the replacement of a character, if it is changed.
+    fn map_char(&self, c: char) -> Option<String> {
+        if let Some(replacement) = self.mapping.get(&c) {
+            return Some(replacement.clone());
+        }
+
+        if self.fullwidth {
+            let ascii = fullwidth_to_ascii(c);
+            if ascii != c {
+                return Some(ascii.to_string());
+            }
+        }
+
+        if self.punct {
+            match simplify_unicode_punct(c) {
+                Conversion::Char(replacement) => return Some(replacement.to_string()),
+                Conversion::String(replacement) => return Some(replacement),
+                Conversion::None(_) => (),
+            }
+        }
+
+        None
+    }
+}
+
+/// Print character changes to stderr, most frequent first.
+fn print_report(changes: Changes) {
+    let mut changes: Vec<_> = changes.into_iter().collect();
+    changes.sort_by(|(change, count), (other_change, other_count)| {
+        other_count
+            .cmp(count)
+            .then_with(|| change.cmp(other_change))
+    });
+
+    for ((original, replacement), count) in changes {
+        let code_points = original
+            .chars()
+            .map(|c| format!("U+{:04X}", c as u32))
+            .collect::<Vec<_>>()
+            .join(" ");
+        eprintln!("{}\t{}\t{}\t{}", original, code_points, replacement, count);
+    }
+}
+
 fn main() {
     let args: Vec<String> = args().collect();
     let program = args[0].clone();
     let mut opts = Options::new();
     opts.optflag("h", "help", "print this help menu");
+    opts.optflag("c", "control", "remove control and zero-width characters");
+    opts.optflag("d", "diacritics", "remove diacritics");
+    opts.optflag("f", "fullwidth", "map fullwidth characters to ASCII");
+    opts.optflag("l", "lemma", "also clean up lemmas");
+    opts.optflag("", "lowercase", "convert to lowercase");
+    opts.optopt(
+        "m",
+        "mapping",
+        "character mapping table (CHAR<TAB>REPLACEMENT), overrides other mappings",
+        "FILE",
+    );
+    opts.optflag(
+        "",
+        "no-punct",
+        "do not replace Unicode punctuation by ASCII equivalents",
+    );
+    opts.optflag(
+        "r",
+        "report",
+        "print the changes of all cleanup steps with their frequencies to stderr",
+    );
     opts.optopt(
         "u",
         "uninorm",
         "unicode normalization: none, nfd, nfkd, nfc, nfkc (default: none)",
         "NORMALIZATION",
     );
+    opts.optflag(
+        "w",
+        "whitespace",
+        "replace whitespace within tokens by single spaces",
+    );
     let matches = or_exit(opts.parse(&args[1..]));
 
     if matches.opt_present("h") {
@@ -54,21 +317,61 @@ fn main() {
         .map(|s| normalization_from(s).or_exit("Unknown normalization", 1))
         .unwrap_or(Normalization::None);
 
-    let input = Input::from(matches.free.get(0));
+    let mapping = match matches.opt_str("m") {
+        Some(path) => read_mapping(BufReader::new(
+            File::open(path).or_exit("Cannot open mapping", 1),
+        ))
+        .or_exit("Cannot read mapping", 1),
+        None => HashMap::new(),
+    };
+
+    let cleanup = Cleanup {
+        invisible: matches.opt_present("c"),
+        norm,
+        mapping,
+        fullwidth: matches.opt_present("f"),
+        punct: !matches.opt_present("no-punct"),
+        diacritics: matches.opt_present("d"),
+        whitespace: matches.opt_present("w"),
+        lowercase: matches.opt_present("lowercase"),
+    };
+    let lemma = matches.opt_present("l");
+    let report = matches.opt_present("r");
+
+    let input = Input::from(matches.free.first());
     let reader = conllx::Reader::new(or_exit(input.buf_read()));
 
     let output = Output::from(matches.free.get(1));
     let mut writer = conllx::Writer::new(BufWriter::new(or_exit(output.write())));
+    let mut changes = Changes::new();
     for sentence in reader {
         let mut sentence = or_exit(sentence);
-        cleanup(&mut sentence, norm);
+        cleanup_sentence(&mut sentence, &cleanup, lemma, &mut changes);
         or_exit(writer.write_sentence(&sentence))
     }
+
+    if report {
+        print_report(changes);
+    }
 }
 
-fn cleanup(sentence: &mut Sentence, norm: Normalization) {
+fn cleanup_sentence(
+    sentence: &mut Sentence,
+    cleanup: &Cleanup,
+    lemma: bool,
+    changes: &mut Changes,
+) {
     for token in sentence {
-        let clean_form = simplify_unicode(token.form(), norm);
-        token.set_form(clean_form);
+        let clean_form = cleanup.clean(token.form(), changes);
+        token.set_form(if clean_form.is_empty() {
+            "_".to_owned()
+        } else {
+            clean_form
+        });
+
+        if lemma {
+            let clean_lemma = token.lemma().map(|lemma| cleanup.clean(lemma, changes));
+            token.set_lemma(clean_lemma.filter(|lemma| !lemma.is_empty()));
+        }
     }
 }
@@ -93,7 +93,7 @@ fn compare_sentences(
     diff_callbacks: &[LayerCallback],
     show_callbacks: &[LayerCallback],
 ) -> Result<(), Error> {
-    for (sent1, sent2) in reader1.into_iter().zip(reader2.into_iter()) {
+    for (sent1, sent2) in reader1.into_iter().zip(reader2) {
         let (sent1, sent2) = (sent1?, sent2?);
 
         let diff = diff_indices(&sent1, &sent2, diff_callbacks)?;
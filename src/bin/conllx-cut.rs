@@ -0,0 +1,217 @@
+use std::collections::HashSet;
+use std::io::BufWriter;
+use std::process;
+
+use clap::{App, AppSettings, Arg, ArgMatches};
+use conllx::{Features, Token, WriteSentence};
+use conllx_utils::{layer_callback, layer_setter, LayerCallback, LayerSetter};
+use failure::Error;
+use stdinout::{Input, OrExit, Output};
+
+static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
+    AppSettings::DontCollapseArgsInUsage,
+    AppSettings::UnifiedHelpMessage,
+];
+
+static LAYERS: &[&str] = &[
+    "form", "lemma", "cpos", "pos", "features", "head", "headrel", "phead", "pheadrel",
+];
+
+static INPUT: &str = "INPUT";
+static OUTPUT: &str = "OUTPUT";
+static CLEAR: &str = "CLEAR";
+static COPY: &str = "COPY";
+static KEEP: &str = "KEEP";
+
+/// A layer or a single feature.
+#[derive(Clone, Eq, Hash, PartialEq)]
+enum Column {
+    Layer(String),
+    Feature(String),
+}
+
+impl Column {
+    fn parse(column: &str) -> Self {
+        if let Some(key) = column.strip_prefix("feature:") {
+            return Column::Feature(key.to_owned());
+        }
+
+        if !LAYERS.contains(&column) {
+            eprintln!("Unknown layer: {}", column);
+            process::exit(1);
+        }
+
+        Column::Layer(column.to_owned())
+    }
+}
+
+pub struct CutApp {
+    input: Option<String>,
+    output: Option<String>,
+    copy: Vec<(LayerCallback, LayerSetter)>,
+    keep: Option<HashSet<Column>>,
+    clear: HashSet<Column>,
+}
+
+impl CutApp {
+    fn new() -> Self {
+        let matches = App::new("conllx-cut")
+            .settings(DEFAULT_CLAP_SETTINGS)
+            .arg(Arg::with_name(INPUT).help("Input data").index(1))
+            .arg(Arg::with_name(OUTPUT).help("Output data").index(2))
+            .arg(
+                Arg::with_name(COPY)
+                    .short("c")
+                    .long("copy")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .value_name("SOURCE:TARGET")
+                    .help("Copy a layer to another layer"),
+            )
+            .arg(
+                Arg::with_name(KEEP)
+                    .short("k")
+                    .long("keep")
+                    .takes_value(true)
+                    .value_name("LAYER[,LAYER]")
+                    .help(
+                        "Clear all layers except the given layers (form, lemma, cpos, pos, \
+                         features, head, headrel, phead, pheadrel, or feature:KEY)",
+                    ),
+            )
+            .arg(
+                Arg::with_name(CLEAR)
+                    .short("r")
+                    .long("clear")
+                    .takes_value(true)
+                    .value_name("LAYER[,LAYER]")
+                    .help(
+                        "Clear the given layers (form, lemma, cpos, pos, features, head, \
+                         headrel, phead, pheadrel, or feature:KEY)",
+                    ),
+            )
+            .get_matches();
+
+        let input = matches.value_of(INPUT).map(ToOwned::to_owned);
+        let output = matches.value_of(OUTPUT).map(ToOwned::to_owned);
+        let copy = matches
+            .values_of(COPY)
+            .map(|values| values.map(copy_callbacks).collect())
+            .unwrap_or_default();
+        let keep = columns(&matches, KEEP);
+        let clear = columns(&matches, CLEAR).unwrap_or_default();
+
+        CutApp {
+            input,
+            output,
+            copy,
+            keep,
+            clear,
+        }
+    }
+
+    fn is_cleared(&self, column: &Column) -> bool {
+        let kept = match self.keep {
+            Some(ref keep) => keep.contains(column),
+            None => true,
+        };
+
+        self.clear.contains(column) || !kept
+    }
+}
+
+fn columns(matches: &ArgMatches, option: &str) -> Option<HashSet<Column>> {
+    matches
+        .value_of(option)
+        .map(|columns| columns.split(',').map(Column::parse).collect())
+}
+
+fn copy_callbacks(spec: &str) -> (LayerCallback, LayerSetter) {
+    let mut parts = spec.splitn(2, ':');
+    let source = parts.next().unwrap();
+    let target = parts.next().or_exit(
+        format!("Copy should be of the form SOURCE:TARGET: {}", spec),
+        1,
+    );
+
+    let callback = layer_callback(source).or_exit(format!("Unknown layer: {}", source), 1);
+    let setter = layer_setter(target).or_exit(format!("Unknown layer: {}", target), 1);
+
+    (callback, setter)
+}
+
+fn main() {
+    let app = CutApp::new();
+
+    let input = Input::from(app.input.as_ref());
+    let reader = conllx::Reader::new(input.buf_read().or_exit("Cannot open input", 1));
+
+    let output = Output::from(app.output.as_ref());
+    let mut writer = conllx::Writer::new(BufWriter::new(
+        output.write().or_exit("Cannot open output", 1),
+    ));
+
+    for sentence in reader {
+        let mut sentence = sentence.or_exit("Cannot read sentence", 1);
+
+        for token in &mut sentence {
+            cut_token(&app, token).or_exit("Cannot process token", 1);
+        }
+
+        writer
+            .write_sentence(&sentence)
+            .or_exit("Cannot write sentence", 1);
+    }
+}
+
+fn cut_token(app: &CutApp, token: &mut Token) -> Result<(), Error> {
+    for (callback, setter) in &app.copy {
+        let value = callback(token).map(|v| v.into_owned());
+        setter(token, value.as_deref())?;
+    }
+
+    for layer in LAYERS {
+        if *layer == "features" {
+            continue;
+        }
+
+        if app.is_cleared(&Column::Layer(layer.to_string())) {
+            layer_setter(layer).unwrap()(token, None)?;
+        }
+    }
+
+    filter_features(app, token);
+
+    Ok(())
+}
+
+/// Remove cleared features of a token.
+fn filter_features(app: &CutApp, token: &mut Token) {
+    let features = match token.features() {
+        Some(features) => features.as_str(),
+        None => return,
+    };
+
+    let all_cleared = app.is_cleared(&Column::Layer("features".to_owned()));
+
+    let retained = features
+        .split('|')
+        .filter(|fv| {
+            let key = fv.split(':').next().unwrap().to_owned();
+            let column = Column::Feature(key);
+            let kept = app.keep.as_ref().map(|keep| keep.contains(&column));
+
+            match kept {
+                Some(true) => !app.clear.contains(&column),
+                _ => !all_cleared && !app.clear.contains(&column),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if retained.is_empty() {
+        token.set_features(None);
+    } else if retained.len() != features.split('|').count() {
+        token.set_features(Some(Features::from_string(retained.join("|"))));
+    }
+}
@@ -33,7 +33,7 @@ fn main() {
         return;
     }
 
-    let input = Input::from(matches.free.get(0));
+    let input = Input::from(matches.free.first());
     let reader = conllx::Reader::new(or_exit(input.buf_read()));
     for sentence in reader {
         let sentence = or_exit(sentence);
@@ -50,10 +50,7 @@ fn check_cycles(sentence: &Sentence, projective: bool) {
             token.head()
         };
 
-        match head {
-            Some(head) => Some((node_index(head), node_index(idx + 1))),
-            None => None,
-        }
+        head.map(|head| (node_index(head), node_index(idx + 1)))
     });
 
     let dep_graph = Graph::<(), (), Directed>::from_edges(edges);
@@ -0,0 +1,410 @@
+use std::borrow::Cow;
+use std::collections::BTreeSet;
+use std::io::{BufRead, BufWriter, Write};
+
+use clap::{App, AppSettings, Arg};
+use conllx::{ReadSentence, Sentence};
+use conllx_utils::{layer_callback, open_reader, sentence_to_graph, LayerCallback};
+use failure::{bail, ensure, Error};
+use petgraph::visit::EdgeRef;
+use stdinout::{OrExit, Output};
+
+static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
+    AppSettings::DontCollapseArgsInUsage,
+    AppSettings::UnifiedHelpMessage,
+];
+
+static INPUT: &str = "INPUT";
+static SYSTEM: &str = "SYSTEM";
+static FORMAT: &str = "FORMAT";
+static LAYERS: &str = "LAYERS";
+static OUTPUT: &str = "OUTPUT";
+static PROJECTIVE: &str = "PROJECTIVE";
+static SENTENCE: &str = "SENTENCE";
+
+/// Output formats.
+#[derive(Clone, Copy)]
+enum Format {
+    Dot,
+    Tikz,
+}
+
+pub struct DotApp {
+    input: String,
+    system: Option<String>,
+    format: Format,
+    layer_callbacks: Vec<LayerCallback>,
+    output: Option<String>,
+    projective: bool,
+    sentence: Option<usize>,
+}
+
+impl DotApp {
+    fn new() -> Self {
+        let matches = App::new("conllx-dot")
+            .settings(DEFAULT_CLAP_SETTINGS)
+            .arg(
+                Arg::with_name(INPUT)
+                    .help("Input data (gold standard when system data is given)")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name(SYSTEM)
+                    .help("System data to overlay")
+                    .index(2),
+            )
+            .arg(
+                Arg::with_name(FORMAT)
+                    .short("f")
+                    .long("format")
+                    .takes_value(true)
+                    .possible_values(&["dot", "tikz"])
+                    .default_value("dot")
+                    .help("Output format: Graphviz DOT or tikz-dependency"),
+            )
+            .arg(
+                Arg::with_name(LAYERS)
+                    .short("l")
+                    .long("layers")
+                    .takes_value(true)
+                    .value_name("LAYER[,LAYER]")
+                    .default_value("form")
+                    .help("Layer(s) to use as node labels (form, lemma, cpos, pos, or features)"),
+            )
+            .arg(
+                Arg::with_name(OUTPUT)
+                    .short("w")
+                    .takes_value(true)
+                    .help("Write output to a file"),
+            )
+            .arg(
+                Arg::with_name(PROJECTIVE)
+                    .short("p")
+                    .long("projective")
+                    .help("Use the projective columns"),
+            )
+            .arg(
+                Arg::with_name(SENTENCE)
+                    .short("s")
+                    .long("sentence")
+                    .takes_value(true)
+                    .help("Only render the sentence with this (1-based) number"),
+            )
+            .get_matches();
+
+        let input = matches.value_of(INPUT).unwrap().to_owned();
+        let system = matches.value_of(SYSTEM).map(ToOwned::to_owned);
+        let format = match matches.value_of(FORMAT).unwrap() {
+            "dot" => Format::Dot,
+            "tikz" => Format::Tikz,
+            _ => unreachable!(),
+        };
+        let layer_callbacks = matches
+            .value_of(LAYERS)
+            .unwrap()
+            .split(',')
+            .map(|layer| layer_callback(layer).or_exit(format!("Unknown layer: {}", layer), 1))
+            .collect();
+        let output = matches.value_of(OUTPUT).map(ToOwned::to_owned);
+        let projective = matches.is_present(PROJECTIVE);
+        let sentence = matches
+            .value_of(SENTENCE)
+            .map(|n| n.parse().or_exit("Cannot parse sentence number", 1));
+
+        DotApp {
+            input,
+            system,
+            format,
+            layer_callbacks,
+            output,
+            projective,
+            sentence,
+        }
+    }
+}
+
+/// A dependency arc: head, dependent, and relation.
+///
+/// Tokens are numbered from 1, the root is 0.
+type Arc = (usize, usize, Option<String>);
+
+/// Arc styles, for overlaying system arcs on gold standard arcs.
+#[derive(Clone, Copy)]
+enum ArcStyle {
+    /// The arc is in both the gold standard and the system data.
+    Both,
+
+    /// The arc is only in the gold standard.
+    Gold,
+
+    /// The arc is only in the system data.
+    System,
+}
+
+impl ArcStyle {
+    fn color(self) -> &'static str {
+        match self {
+            ArcStyle::Both => "black",
+            ArcStyle::Gold => "blue",
+            ArcStyle::System => "red",
+        }
+    }
+}
+
+fn main() {
+    let app = DotApp::new();
+
+    let reader = open_reader(&app.input).or_exit("Cannot open input", 1);
+    let system_reader = app
+        .system
+        .as_ref()
+        .map(|system| open_reader(system).or_exit("Cannot open system data", 1));
+
+    let output = Output::from(app.output.as_ref());
+    let mut writer = BufWriter::new(output.write().or_exit("Cannot open output", 1));
+
+    render_sentences(&app, reader, system_reader, &mut writer)
+        .or_exit("Cannot render sentences", 1);
+}
+
+fn render_sentences(
+    app: &DotApp,
+    reader: conllx::Reader<impl BufRead>,
+    mut system_reader: Option<conllx::Reader<impl BufRead>>,
+    writer: &mut impl Write,
+) -> Result<(), Error> {
+    for (sent_idx, sentence) in reader.into_iter().enumerate() {
+        let sentence = sentence?;
+
+        let system = match system_reader {
+            Some(ref mut system_reader) => match system_reader.read_sentence()? {
+                Some(system) => Some(system),
+                None => bail!("System data ends before sentence {}", sent_idx + 1),
+            },
+            None => None,
+        };
+
+        if let Some(n) = app.sentence {
+            if n != sent_idx + 1 {
+                continue;
+            }
+        }
+
+        if let Some(ref system) = system {
+            ensure!(
+                sentence.len() == system.len(),
+                "Sentence {}: different number of tokens: {} {}",
+                sent_idx + 1,
+                sentence.len(),
+                system.len()
+            );
+        }
+
+        let arcs = styled_arcs(&sentence, system.as_ref(), app.projective);
+        let labels = node_labels(&sentence, &app.layer_callbacks);
+
+        match app.format {
+            Format::Dot => write_dot(writer, sent_idx, &labels, &arcs)?,
+            Format::Tikz => write_tikz(writer, &labels, &arcs)?,
+        }
+
+        if app.sentence.is_some() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+fn arcs(sentence: &Sentence, projective: bool) -> BTreeSet<Arc> {
+    let graph = sentence_to_graph(sentence, projective);
+
+    let mut arcs: BTreeSet<Arc> = graph
+        .edge_references()
+        .map(|edge| {
+            (
+                graph[edge.source()].offset + 1,
+                graph[edge.target()].offset + 1,
+                edge.weight().map(ToOwned::to_owned),
+            )
+        })
+        .collect();
+
+    // Root attachments are not in the dependency graph.
+    for (idx, token) in sentence.iter().enumerate() {
+        let (head, rel) = if projective {
+            (token.p_head(), token.p_head_rel())
+        } else {
+            (token.head(), token.head_rel())
+        };
+
+        if head == Some(0) {
+            arcs.insert((0, idx + 1, rel.map(ToOwned::to_owned)));
+        }
+    }
+
+    arcs
+}
+
+fn styled_arcs(
+    sentence: &Sentence,
+    system: Option<&Sentence>,
+    projective: bool,
+) -> Vec<(Arc, ArcStyle)> {
+    let gold_arcs = arcs(sentence, projective);
+    let system_arcs = match system {
+        Some(system) => arcs(system, projective),
+        None => {
+            return gold_arcs
+                .into_iter()
+                .map(|arc| (arc, ArcStyle::Both))
+                .collect()
+        }
+    };
+
+    let mut styled = Vec::new();
+    for arc in &gold_arcs {
+        if system_arcs.contains(arc) {
+            styled.push((arc.clone(), ArcStyle::Both));
+        } else {
+            styled.push((arc.clone(), ArcStyle::Gold));
+        }
+    }
+
+    for arc in system_arcs.difference(&gold_arcs) {
+        styled.push((arc.clone(), ArcStyle::System));
+    }
+
+    styled
+}
+
+fn node_labels(sentence: &Sentence, callbacks: &[LayerCallback]) -> Vec<Vec<String>> {
+    sentence
+        .iter()
+        .map(|token| {
+            callbacks
+                .iter()
+                .map(|callback| callback(token).unwrap_or(Cow::Borrowed("_")).into_owned())
+                .collect()
+        })
+        .collect()
+}
+
+fn write_dot(
+    writer: &mut impl Write,
+    sent_idx: usize,
+    labels: &[Vec<String>],
+    arcs: &[(Arc, ArcStyle)],
+) -> Result<(), Error> {
+    writeln!(writer, "digraph sentence{} {{", sent_idx + 1)?;
+    writeln!(writer, "  ordering=out;")?;
+    writeln!(writer, "  node [shape=box];")?;
+    writeln!(writer, "  n0 [label=\"ROOT\"];")?;
+
+    for (idx, token_labels) in labels.iter().enumerate() {
+        writeln!(
+            writer,
+            "  n{} [label=\"{}\"];",
+            idx + 1,
+            token_labels
+                .iter()
+                .map(|label| escape_dot(label))
+                .collect::<Vec<_>>()
+                .join("\\n")
+        )?;
+    }
+
+    for ((head, dependent, rel), style) in arcs {
+        writeln!(
+            writer,
+            "  n{} -> n{} [label=\"{}\", color={}, fontcolor={}];",
+            head,
+            dependent,
+            escape_dot(rel.as_ref().map(String::as_str).unwrap_or("_")),
+            style.color(),
+            style.color()
+        )?;
+    }
+
+    writeln!(writer, "}}")?;
+
+    Ok(())
+}
+
+fn write_tikz(
+    writer: &mut impl Write,
+    labels: &[Vec<String>],
+    arcs: &[(Arc, ArcStyle)],
+) -> Result<(), Error> {
+    writeln!(writer, "\\begin{{dependency}}")?;
+    writeln!(writer, "  \\begin{{deptext}}")?;
+
+    let n_layers = labels.first().map(Vec::len).unwrap_or(0);
+    for layer in 0..n_layers {
+        writeln!(
+            writer,
+            "    {} \\\\",
+            labels
+                .iter()
+                .map(|token_labels| escape_latex(&token_labels[layer]))
+                .collect::<Vec<_>>()
+                .join(" \\& ")
+        )?;
+    }
+
+    writeln!(writer, "  \\end{{deptext}}")?;
+
+    for ((head, dependent, rel), style) in arcs {
+        // System arcs are drawn below the sentence.
+        let options = match style {
+            ArcStyle::Both => String::new(),
+            ArcStyle::Gold => format!("[edge style={{{}}}]", style.color()),
+            ArcStyle::System => format!(
+                "[edge below, edge style={{{}}}, label style={{text={}}}]",
+                style.color(),
+                style.color()
+            ),
+        };
+
+        let rel = escape_latex(rel.as_ref().map(String::as_str).unwrap_or("_"));
+
+        if *head == 0 {
+            writeln!(writer, "  \\deproot{}{{{}}}{{{}}}", options, dependent, rel)?;
+        } else {
+            writeln!(
+                writer,
+                "  \\depedge{}{{{}}}{{{}}}{{{}}}",
+                options, head, dependent, rel
+            )?;
+        }
+    }
+
+    writeln!(writer, "\\end{{dependency}}")?;
+    writeln!(writer)?;
+
+    Ok(())
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_latex(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\textbackslash{}"),
+            '~' => escaped.push_str("\\textasciitilde{}"),
+            '^' => escaped.push_str("\\textasciicircum{}"),
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter};
+
+use clap::{App, AppSettings, Arg};
+use conllx::{Features, Token, WriteSentence};
+use conllx_utils::{
+    features_to_string, layer_callback, parse_features, FeatureList, LayerCallback,
+};
+use failure::{bail, format_err, Error};
+use regex::Regex;
+use stdinout::{Input, OrExit, Output};
+
+static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
+    AppSettings::DontCollapseArgsInUsage,
+    AppSettings::UnifiedHelpMessage,
+];
+
+static MAPPING: &str = "MAPPING";
+static INPUT: &str = "INPUT";
+static OUTPUT: &str = "OUTPUT";
+static FEATURE: &str = "FEATURE";
+static LAYER: &str = "LAYER";
+static NO_ORIGINAL: &str = "NO_ORIGINAL";
+static REGEX: &str = "REGEX";
+
+/// Mapping from compact tags to features.
+enum Mapping {
+    /// Map the symbol at each position of a tag to a feature.
+    Position(HashMap<(usize, char), (String, String)>),
+
+    /// Add a feature when a regular expression matches a tag.
+    Regex(Vec<(Regex, String, String)>),
+}
+
+impl Mapping {
+    /// Read a position-based mapping.
+    ///
+    /// Each line has the form `POSITION SYMBOL FEATURE VALUE`, where
+    /// the fields are tab-separated and positions are 1-based.
+    fn read_positions(read: impl BufRead) -> Result<Self, Error> {
+        let mut mapping = HashMap::new();
+
+        for fields in read_table(read, 4)? {
+            let position: usize = fields[0]
+                .parse()
+                .map_err(|_| format_err!("Cannot parse position: {}", fields[0]))?;
+            if position == 0 {
+                bail!("Positions are 1-based, got: 0");
+            }
+
+            let mut symbol_chars = fields[1].chars();
+            let symbol = match (symbol_chars.next(), symbol_chars.next()) {
+                (Some(symbol), None) => symbol,
+                _ => bail!("Symbol should be a single character: {}", fields[1]),
+            };
+
+            mapping.insert(
+                (position - 1, symbol),
+                (fields[2].clone(), fields[3].clone()),
+            );
+        }
+
+        Ok(Mapping::Position(mapping))
+    }
+
+    /// Read a regular expression-based mapping.
+    ///
+    /// Each line has the form `REGEX FEATURE VALUE`, where the fields are
+    /// tab-separated. The value can refer to capture groups, e.g. `$1`.
+    fn read_regexes(read: impl BufRead) -> Result<Self, Error> {
+        let mut mapping = Vec::new();
+
+        for fields in read_table(read, 3)? {
+            let re = Regex::new(&fields[0])?;
+            mapping.push((re, fields[1].clone(), fields[2].clone()));
+        }
+
+        Ok(Mapping::Regex(mapping))
+    }
+
+    fn expand(&self, tag: &str) -> FeatureList {
+        match self {
+            Mapping::Position(mapping) => tag
+                .chars()
+                .enumerate()
+                .filter_map(|(idx, symbol)| mapping.get(&(idx, symbol)))
+                .map(|(feature, value)| (feature.clone(), Some(value.clone())))
+                .collect(),
+            Mapping::Regex(mapping) => mapping
+                .iter()
+                .filter_map(|(re, feature, value)| {
+                    re.captures(tag).map(|captures| {
+                        let mut expanded = String::new();
+                        captures.expand(value, &mut expanded);
+                        (feature.clone(), Some(expanded))
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+fn read_table(read: impl BufRead, n_fields: usize) -> Result<Vec<Vec<String>>, Error> {
+    let mut table = Vec::new();
+
+    for line in read.lines() {
+        let line = line?;
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<_> = line.split('\t').map(ToOwned::to_owned).collect();
+        if fields.len() != n_fields {
+            bail!("Expected {} fields, got: {}", n_fields, line);
+        }
+
+        table.push(fields);
+    }
+
+    Ok(table)
+}
+
+pub struct ExpandMorphApp {
+    mapping: Mapping,
+    input: Option<String>,
+    output: Option<String>,
+    feature: Option<String>,
+    layer: String,
+    layer_callback: LayerCallback,
+}
+
+impl ExpandMorphApp {
+    fn new() -> Self {
+        let matches = App::new("conllx-expand-morph")
+            .settings(DEFAULT_CLAP_SETTINGS)
+            .arg(
+                Arg::with_name(MAPPING)
+                    .help("Tag to feature mapping")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(Arg::with_name(INPUT).help("Input data").index(2))
+            .arg(Arg::with_name(OUTPUT).help("Output data").index(3))
+            .arg(
+                Arg::with_name(FEATURE)
+                    .short("f")
+                    .long("feature")
+                    .takes_value(true)
+                    .default_value("morph")
+                    .help("Feature to store the original tag in"),
+            )
+            .arg(
+                Arg::with_name(LAYER)
+                    .short("l")
+                    .long("layer")
+                    .takes_value(true)
+                    .possible_values(&["features", "lemma", "cpos", "pos"])
+                    .default_value("features")
+                    .help("Layer with the compact tags"),
+            )
+            .arg(
+                Arg::with_name(NO_ORIGINAL)
+                    .short("n")
+                    .long("no-original")
+                    .help("Do not include the original tag as a feature"),
+            )
+            .arg(
+                Arg::with_name(REGEX)
+                    .short("r")
+                    .long("regex")
+                    .help("The mapping consists of regular expressions"),
+            )
+            .get_matches();
+
+        let mapping_read = BufReader::new(
+            File::open(matches.value_of(MAPPING).unwrap()).or_exit("Cannot open mapping", 1),
+        );
+        let mapping = if matches.is_present(REGEX) {
+            Mapping::read_regexes(mapping_read)
+        } else {
+            Mapping::read_positions(mapping_read)
+        }
+        .or_exit("Cannot read mapping", 1);
+
+        let input = matches.value_of(INPUT).map(ToOwned::to_owned);
+        let output = matches.value_of(OUTPUT).map(ToOwned::to_owned);
+        let feature = if matches.is_present(NO_ORIGINAL) {
+            None
+        } else {
+            matches.value_of(FEATURE).map(ToOwned::to_owned)
+        };
+        let layer = matches.value_of(LAYER).unwrap().to_owned();
+        let layer_callback = layer_callback(&layer).expect("Unknown layer");
+
+        ExpandMorphApp {
+            mapping,
+            input,
+            output,
+            feature,
+            layer,
+            layer_callback,
+        }
+    }
+}
+
+fn main() {
+    let app = ExpandMorphApp::new();
+
+    let input = Input::from(app.input.as_ref());
+    let reader = conllx::Reader::new(input.buf_read().or_exit("Cannot open input", 1));
+
+    let output = Output::from(app.output.as_ref());
+    let mut writer = conllx::Writer::new(BufWriter::new(
+        output.write().or_exit("Cannot open output", 1),
+    ));
+
+    let mut skipped = 0;
+    for sentence in reader {
+        let mut sentence = sentence.or_exit("Cannot read sentence", 1);
+
+        for token in &mut sentence {
+            if !expand_token(&app, token) {
+                skipped += 1;
+            }
+        }
+
+        writer
+            .write_sentence(&sentence)
+            .or_exit("Cannot write sentence", 1);
+    }
+
+    if skipped != 0 {
+        eprintln!(
+            "Skipped {} token(s) with a tag that contains '|' or ':'",
+            skipped
+        );
+    }
+}
+
+/// Expand the tag of a token, returning `false` if the token is skipped.
+///
+/// Tags that contain `|` or `:` are not expanded, since they are
+/// probably features already and cannot be stored as a feature value.
+fn expand_token(app: &ExpandMorphApp, token: &mut Token) -> bool {
+    let tag = match (app.layer_callback)(token) {
+        Some(tag) => tag.into_owned(),
+        None => return true,
+    };
+
+    if tag.contains(&['|', ':'][..]) {
+        return false;
+    }
+
+    // When the tags are stored in the features layer, the expansion
+    // replaces the tags.
+    let mut features = if app.layer == "features" {
+        Vec::new()
+    } else {
+        token
+            .features()
+            .map(|f| parse_features(f.as_str(), ':'))
+            .unwrap_or_default()
+    };
+
+    features.extend(app.mapping.expand(&tag));
+
+    if let Some(ref feature) = app.feature {
+        features.push((feature.clone(), Some(tag)));
+    }
+
+    token.set_features(features_to_string(&features, ':').map(Features::from_string));
+
+    true
+}
@@ -0,0 +1,226 @@
+use std::collections::HashSet;
+use std::io::BufWriter;
+
+use clap::{App, AppSettings, Arg, ArgMatches};
+use conllx::{Features, Token, WriteSentence};
+use conllx_utils::{features_to_string, parse_features, sort_features, FeatureList};
+use stdinout::{Input, OrExit, Output};
+
+static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
+    AppSettings::DontCollapseArgsInUsage,
+    AppSettings::UnifiedHelpMessage,
+];
+
+static INPUT: &str = "INPUT";
+static OUTPUT: &str = "OUTPUT";
+static ADD: &str = "ADD";
+static INPUT_SEPARATOR: &str = "INPUT_SEPARATOR";
+static KEEP: &str = "KEEP";
+static OUTPUT_SEPARATOR: &str = "OUTPUT_SEPARATOR";
+static REMOVE: &str = "REMOVE";
+static RENAME: &str = "RENAME";
+static SORT: &str = "SORT";
+
+pub struct FeaturesApp {
+    input: Option<String>,
+    output: Option<String>,
+    add: Vec<(String, Option<String>)>,
+    input_separator: char,
+    keep: Option<HashSet<String>>,
+    output_separator: char,
+    remove: HashSet<String>,
+    rename: Vec<(String, String)>,
+    sort: bool,
+}
+
+impl FeaturesApp {
+    fn new() -> Self {
+        let matches = App::new("conllx-features")
+            .settings(DEFAULT_CLAP_SETTINGS)
+            .arg(Arg::with_name(INPUT).help("Input data").index(1))
+            .arg(Arg::with_name(OUTPUT).help("Output data").index(2))
+            .arg(
+                Arg::with_name(ADD)
+                    .short("a")
+                    .long("add")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .value_name("FEATURE[=VALUE]")
+                    .help("Add a feature, replacing its value if it is present"),
+            )
+            .arg(
+                Arg::with_name(REMOVE)
+                    .short("d")
+                    .long("remove")
+                    .takes_value(true)
+                    .value_name("FEATURE[,FEATURE]")
+                    .help("Remove features"),
+            )
+            .arg(
+                Arg::with_name(KEEP)
+                    .short("k")
+                    .long("keep")
+                    .takes_value(true)
+                    .value_name("FEATURE[,FEATURE]")
+                    .help("Remove all features except the given features"),
+            )
+            .arg(
+                Arg::with_name(RENAME)
+                    .short("r")
+                    .long("rename")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .value_name("OLD=NEW")
+                    .help("Rename a feature"),
+            )
+            .arg(
+                Arg::with_name(SORT)
+                    .short("s")
+                    .long("sort")
+                    .help("Sort features canonically"),
+            )
+            .arg(
+                Arg::with_name(INPUT_SEPARATOR)
+                    .short("i")
+                    .long("input-separator")
+                    .takes_value(true)
+                    .possible_values(&[":", "="])
+                    .default_value(":")
+                    .help("Feature-value separator of the input"),
+            )
+            .arg(
+                Arg::with_name(OUTPUT_SEPARATOR)
+                    .short("o")
+                    .long("output-separator")
+                    .takes_value(true)
+                    .possible_values(&[":", "="])
+                    .default_value(":")
+                    .help("Feature-value separator of the output"),
+            )
+            .get_matches();
+
+        let input = matches.value_of(INPUT).map(ToOwned::to_owned);
+        let output = matches.value_of(OUTPUT).map(ToOwned::to_owned);
+        let add = matches
+            .values_of(ADD)
+            .map(|values| {
+                values
+                    .map(|fv| {
+                        let mut iter = fv.splitn(2, '=');
+                        let feature = iter.next().unwrap().to_owned();
+                        (feature, iter.next().map(ToOwned::to_owned))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let input_separator = separator(&matches, INPUT_SEPARATOR);
+        let keep = feature_set(&matches, KEEP);
+        let output_separator = separator(&matches, OUTPUT_SEPARATOR);
+        let remove = feature_set(&matches, REMOVE).unwrap_or_default();
+        let rename = matches
+            .values_of(RENAME)
+            .map(|values| {
+                values
+                    .map(|spec| {
+                        let mut iter = spec.splitn(2, '=');
+                        let old = iter.next().unwrap().to_owned();
+                        let new = iter
+                            .next()
+                            .or_exit(format!("Rename should be of the form OLD=NEW: {}", spec), 1)
+                            .to_owned();
+                        (old, new)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let sort = matches.is_present(SORT);
+
+        FeaturesApp {
+            input,
+            output,
+            add,
+            input_separator,
+            keep,
+            output_separator,
+            remove,
+            rename,
+            sort,
+        }
+    }
+}
+
+fn feature_set(matches: &ArgMatches, option: &str) -> Option<HashSet<String>> {
+    matches
+        .value_of(option)
+        .map(|features| features.split(',').map(ToOwned::to_owned).collect())
+}
+
+fn separator(matches: &ArgMatches, option: &str) -> char {
+    matches.value_of(option).unwrap().chars().next().unwrap()
+}
+
+fn main() {
+    let app = FeaturesApp::new();
+
+    let input = Input::from(app.input.as_ref());
+    let reader = conllx::Reader::new(input.buf_read().or_exit("Cannot open input", 1));
+
+    let output = Output::from(app.output.as_ref());
+    let mut writer = conllx::Writer::new(BufWriter::new(
+        output.write().or_exit("Cannot open output", 1),
+    ));
+
+    for sentence in reader {
+        let mut sentence = sentence.or_exit("Cannot read sentence", 1);
+
+        for token in &mut sentence {
+            process_features(&app, token);
+        }
+
+        writer
+            .write_sentence(&sentence)
+            .or_exit("Cannot write sentence", 1);
+    }
+}
+
+fn process_features(app: &FeaturesApp, token: &mut Token) {
+    let mut features = token
+        .features()
+        .map(|f| parse_features(f.as_str(), app.input_separator))
+        .unwrap_or_default();
+
+    features.retain(|(feature, _)| !app.remove.contains(feature));
+
+    if let Some(ref keep) = app.keep {
+        features.retain(|(feature, _)| keep.contains(feature));
+    }
+
+    for (old, new) in &app.rename {
+        for (feature, _) in &mut features {
+            if feature == old {
+                *feature = new.clone();
+            }
+        }
+    }
+
+    for (feature, value) in &app.add {
+        add_feature(&mut features, feature, value.as_ref());
+    }
+
+    if app.sort {
+        sort_features(&mut features);
+    }
+
+    token.set_features(
+        features_to_string(&features, app.output_separator).map(Features::from_string),
+    );
+}
+
+fn add_feature(features: &mut FeatureList, feature: &str, value: Option<&String>) {
+    match features.iter_mut().find(|(f, _)| f == feature) {
+        Some((_, v)) => *v = value.cloned(),
+        None => features.push((feature.to_owned(), value.cloned())),
+    }
+}
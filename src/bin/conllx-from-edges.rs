@@ -0,0 +1,210 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Lines};
+
+use clap::{App, AppSettings, Arg};
+use conllx::Sentence;
+use conllx_utils::{CommentedReader, CommentedWriter};
+use failure::{bail, ensure, format_err, Error};
+use stdinout::{OrExit, Output};
+
+static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
+    AppSettings::DontCollapseArgsInUsage,
+    AppSettings::UnifiedHelpMessage,
+];
+
+static TOKENS: &str = "TOKENS";
+static EDGES: &str = "EDGES";
+static OUTPUT: &str = "OUTPUT";
+static PROJECTIVE: &str = "PROJECTIVE";
+
+pub struct FromEdgesApp {
+    tokens: String,
+    edges: String,
+    output: Option<String>,
+    projective: bool,
+}
+
+impl FromEdgesApp {
+    fn new() -> Self {
+        let matches = App::new("conllx-from-edges")
+            .settings(DEFAULT_CLAP_SETTINGS)
+            .arg(
+                Arg::with_name(TOKENS)
+                    .help("Tokens (CoNLL-X)")
+                    .index(1)
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name(EDGES)
+                    .help(
+                        "Edges (dependent, head, relation), one sentence per block, \
+                         blocks separated by empty lines",
+                    )
+                    .index(2)
+                    .required(true),
+            )
+            .arg(Arg::with_name(OUTPUT).help("Output data").index(3))
+            .arg(
+                Arg::with_name(PROJECTIVE)
+                    .short("p")
+                    .long("projective")
+                    .help("Store the edges in the projective columns"),
+            )
+            .get_matches();
+
+        FromEdgesApp {
+            tokens: matches.value_of(TOKENS).unwrap().to_owned(),
+            edges: matches.value_of(EDGES).unwrap().to_owned(),
+            output: matches.value_of(OUTPUT).map(ToOwned::to_owned),
+            projective: matches.is_present(PROJECTIVE),
+        }
+    }
+}
+
+/// A (dependent, head, relation) edge.
+type Edge = (usize, usize, String);
+
+/// Reader for blocks of edges, separated by empty lines.
+struct EdgeReader<R> {
+    lines: Lines<R>,
+    line_idx: usize,
+}
+
+impl<R: BufRead> EdgeReader<R> {
+    fn new(read: R) -> Self {
+        EdgeReader {
+            lines: read.lines(),
+            line_idx: 0,
+        }
+    }
+
+    /// Read the edges of a sentence.
+    ///
+    /// Returns `None` when there are no more sentences.
+    fn read_edges(&mut self) -> Result<Option<Vec<Edge>>, Error> {
+        let mut edges = Vec::new();
+
+        for line in &mut self.lines {
+            let line = line?;
+            self.line_idx += 1;
+
+            if line.trim().is_empty() {
+                if edges.is_empty() {
+                    continue;
+                }
+
+                return Ok(Some(edges));
+            }
+
+            let line_idx = self.line_idx;
+            edges.push(parse_edge(&line).map_err(|err| format_err!("Line {}: {}", line_idx, err))?);
+        }
+
+        if edges.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(edges))
+        }
+    }
+}
+
+fn parse_edge(line: &str) -> Result<Edge, Error> {
+    let parts: Vec<_> = line.split('\t').collect();
+    ensure!(
+        parts.len() == 3,
+        "expected dependent, head, and relation, got: {}",
+        line
+    );
+
+    let dependent = parts[0]
+        .parse()
+        .map_err(|_| format_err!("cannot parse dependent: {}", parts[0]))?;
+    let head = parts[1]
+        .parse()
+        .map_err(|_| format_err!("cannot parse head: {}", parts[1]))?;
+
+    Ok((dependent, head, parts[2].to_owned()))
+}
+
+/// Attach the tokens of a sentence using the given edges.
+///
+/// Every token must be the dependent of exactly one edge.
+fn attach(sentence: &mut Sentence, edges: Vec<Edge>, projective: bool) -> Result<(), Error> {
+    let mut attached = vec![false; sentence.len()];
+
+    for (dependent, head, rel) in edges {
+        ensure!(
+            dependent >= 1 && dependent <= sentence.len(),
+            "dependent {} is not a token",
+            dependent
+        );
+        ensure!(head <= sentence.len(), "head {} is not a token", head);
+        ensure!(
+            dependent != head,
+            "token {} is attached to itself",
+            dependent
+        );
+        ensure!(
+            !attached[dependent - 1],
+            "token {} has more than one head",
+            dependent
+        );
+        attached[dependent - 1] = true;
+
+        let token = &mut sentence[dependent - 1];
+        if projective {
+            token.set_p_head(Some(head));
+            token.set_p_head_rel(Some(rel));
+        } else {
+            token.set_head(Some(head));
+            token.set_head_rel(Some(rel));
+        }
+    }
+
+    if let Some(idx) = attached.iter().position(|&attached| !attached) {
+        bail!("token {} has no head", idx + 1);
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let app = FromEdgesApp::new();
+
+    let reader = CommentedReader::new(BufReader::new(
+        File::open(&app.tokens).or_exit("Cannot open tokens", 1),
+    ));
+    let mut edge_reader = EdgeReader::new(BufReader::new(
+        File::open(&app.edges).or_exit("Cannot open edges", 1),
+    ));
+
+    let output = Output::from(app.output.as_ref());
+    let mut writer = CommentedWriter::new(BufWriter::new(
+        output.write().or_exit("Cannot open output", 1),
+    ));
+
+    for (sent_idx, sentence) in reader.enumerate() {
+        let mut sentence = sentence.or_exit("Cannot read sentence", 1);
+
+        let edges = edge_reader
+            .read_edges()
+            .or_exit("Cannot read edges", 1)
+            .or_exit(format!("No edges for sentence {}", sent_idx + 1), 1);
+
+        attach(&mut sentence.sentence, edges, app.projective)
+            .or_exit(format!("Sentence {}", sent_idx + 1), 1);
+
+        writer
+            .write_sentence(&sentence)
+            .or_exit("Cannot write sentence", 1);
+    }
+
+    if edge_reader
+        .read_edges()
+        .or_exit("Cannot read edges", 1)
+        .is_some()
+    {
+        eprintln!("There are more edge blocks than sentences");
+        std::process::exit(1);
+    }
+}
@@ -1,9 +1,12 @@
-use std::io::{BufRead, BufWriter};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::process;
 
 use clap::{App, AppSettings, Arg};
 use conllx_ng::graph::Sentence;
-use conllx_ng::io::WriteSentence;
-use conllx_ng::token::TokenBuilder;
+use conllx_ng::token::{Token, TokenBuilder};
+use conllx_utils::Tokenizer;
+use failure::{format_err, Error};
 use stdinout::{Input, OrExit, Output};
 
 static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
@@ -13,10 +16,66 @@ static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
 
 static INPUT: &str = "INPUT";
 static OUTPUT: &str = "OUTPUT";
+static ABBREVIATIONS: &str = "ABBREVIATIONS";
+static COMMENTS: &str = "COMMENTS";
+static MODE: &str = "MODE";
+static TAG_LAYER: &str = "TAG_LAYER";
+static TAG_SEPARATOR: &str = "TAG_SEPARATOR";
+
+/// Input modes.
+#[derive(Clone, Copy)]
+enum Mode {
+    /// One sentence per line, tokens separated by whitespace.
+    Tokenized,
+
+    /// Raw text, paragraphs separated by empty lines.
+    Raw,
+}
+
+/// Layers that tags of pre-tagged text can be stored in.
+#[derive(Clone, Copy)]
+enum TagLayer {
+    Lemma,
+    CPos,
+    Pos,
+}
+
+/// Format of pre-tagged text, such as `word/TAG`.
+struct Tagging {
+    separator: String,
+    layer: TagLayer,
+}
+
+impl Tagging {
+    /// Construct a token from a tagged word.
+    ///
+    /// The word is split at the last occurrence of the separator, so that
+    /// the separator can occur in the word itself.
+    fn token(&self, tagged: &str) -> Option<Token> {
+        let idx = tagged.rfind(&self.separator)?;
+        let (form, tag) = (&tagged[..idx], &tagged[idx + self.separator.len()..]);
+        if form.is_empty() || tag.is_empty() {
+            return None;
+        }
+
+        let builder = TokenBuilder::new(form);
+        let builder = match self.layer {
+            TagLayer::Lemma => builder.lemma(tag),
+            TagLayer::CPos => builder.cpos(tag),
+            TagLayer::Pos => builder.pos(tag),
+        };
+
+        Some(builder.into())
+    }
+}
 
 pub struct FromTextApp {
     input: Option<String>,
     output: Option<String>,
+    abbreviations: Option<String>,
+    comments: bool,
+    mode: Mode,
+    tagging: Option<Tagging>,
 }
 
 impl FromTextApp {
@@ -25,43 +84,257 @@ impl FromTextApp {
             .settings(DEFAULT_CLAP_SETTINGS)
             .arg(Arg::with_name(INPUT).help("Input data").index(1))
             .arg(Arg::with_name(OUTPUT).help("Output data").index(2))
+            .arg(
+                Arg::with_name(ABBREVIATIONS)
+                    .short("a")
+                    .long("abbreviations")
+                    .takes_value(true)
+                    .value_name("FILE")
+                    .help("Additional abbreviations for raw text, one per line"),
+            )
+            .arg(
+                Arg::with_name(COMMENTS)
+                    .short("c")
+                    .long("comments")
+                    .help("Write '# text' comments, and '# newpar' comments for raw text"),
+            )
+            .arg(
+                Arg::with_name(MODE)
+                    .short("m")
+                    .long("mode")
+                    .takes_value(true)
+                    .possible_values(&["tokenized", "raw"])
+                    .default_value("tokenized")
+                    .help(
+                        "Input mode: one tokenized sentence per line, or raw text that is \
+                         tokenized and split into sentences (paragraphs are separated by \
+                         empty lines)",
+                    ),
+            )
+            .arg(
+                Arg::with_name(TAG_LAYER)
+                    .long("tag-layer")
+                    .takes_value(true)
+                    .possible_values(&["lemma", "cpos", "pos"])
+                    .default_value("pos")
+                    .help("Layer to store the tags of pre-tagged text in"),
+            )
+            .arg(
+                Arg::with_name(TAG_SEPARATOR)
+                    .short("t")
+                    .long("tag-separator")
+                    .takes_value(true)
+                    .value_name("SEP")
+                    .help("Read pre-tagged tokens, such as word/TAG (SEP is /) or word_TAG"),
+            )
             .get_matches();
 
         let input = matches.value_of(INPUT).map(ToOwned::to_owned);
         let output = matches.value_of(OUTPUT).map(ToOwned::to_owned);
+        let abbreviations = matches.value_of(ABBREVIATIONS).map(ToOwned::to_owned);
+        let comments = matches.is_present(COMMENTS);
+        let mode = match matches.value_of(MODE).unwrap() {
+            "tokenized" => Mode::Tokenized,
+            "raw" => Mode::Raw,
+            _ => unreachable!(),
+        };
+        let tagging = matches.value_of(TAG_SEPARATOR).map(|separator| {
+            if separator.is_empty() {
+                eprintln!("The tag separator cannot be empty");
+                process::exit(1);
+            }
+
+            let layer = match matches.value_of(TAG_LAYER).unwrap() {
+                "lemma" => TagLayer::Lemma,
+                "cpos" => TagLayer::CPos,
+                "pos" => TagLayer::Pos,
+                _ => unreachable!(),
+            };
+
+            Tagging {
+                separator: separator.to_owned(),
+                layer,
+            }
+        });
+
+        if tagging.is_some() {
+            if let Mode::Raw = mode {
+                eprintln!("Pre-tagged text must be tokenized");
+                process::exit(1);
+            }
+        }
+
+        FromTextApp {
+            input,
+            output,
+            abbreviations,
+            comments,
+            mode,
+            tagging,
+        }
+    }
+}
+
+/// Writer for sentences that are preceded by comments.
+struct SentenceWriter<W> {
+    write: W,
+    comments: bool,
+    first: bool,
+}
+
+impl<W: Write> SentenceWriter<W> {
+    fn new(write: W, comments: bool) -> Self {
+        SentenceWriter {
+            write,
+            comments,
+            first: true,
+        }
+    }
+
+    /// Write a sentence. Comments are only written when enabled.
+    fn write_sentence(&mut self, comments: &[String], sentence: &Sentence) -> Result<(), Error> {
+        if self.first {
+            self.first = false;
+        } else {
+            writeln!(self.write)?;
+        }
 
-        FromTextApp { input, output }
+        if self.comments {
+            for comment in comments {
+                writeln!(self.write, "{}", comment)?;
+            }
+        }
+
+        write!(self.write, "{}", sentence)?;
+
+        Ok(())
     }
 }
 
 fn main() {
     let app = FromTextApp::new();
 
-    let input = Input::from(app.input);
+    let input = Input::from(app.input.as_ref());
     let reader = input.buf_read().or_exit("Cannot open input", 1);
 
-    let output = Output::from(app.output);
-    let mut writer = conllx_ng::io::Writer::new(BufWriter::new(
-        output.write().or_exit("Cannot open output", 1),
-    ));
+    let output = Output::from(app.output.as_ref());
+    let mut writer = SentenceWriter::new(
+        BufWriter::new(output.write().or_exit("Cannot open output", 1)),
+        app.comments,
+    );
+
+    match app.mode {
+        Mode::Tokenized => convert_tokenized(app.tagging.as_ref(), reader, &mut writer),
+        Mode::Raw => {
+            let tokenizer = tokenizer(&app).or_exit("Cannot read abbreviations", 1);
+            convert_raw(&tokenizer, reader, &mut writer)
+        }
+    }
+    .or_exit("Cannot convert text", 1);
+}
+
+fn tokenizer(app: &FromTextApp) -> Result<Tokenizer, Error> {
+    let mut tokenizer = Tokenizer::new();
+
+    if let Some(ref path) = app.abbreviations {
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                tokenizer.add_abbreviation(&line);
+            }
+        }
+    }
+
+    Ok(tokenizer)
+}
+
+fn convert_tokenized(
+    tagging: Option<&Tagging>,
+    reader: impl BufRead,
+    writer: &mut SentenceWriter<impl Write>,
+) -> Result<(), Error> {
+    for (line_idx, line) in reader.lines().enumerate() {
+        let line = line?;
+
+        let words: Vec<_> = line.split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+
+        let tokens = match tagging {
+            Some(tagging) => words
+                .iter()
+                .map(|&word| {
+                    tagging.token(word).ok_or_else(|| {
+                        format_err!("Line {}: cannot split tag from: {}", line_idx + 1, word)
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            None => words.iter().map(|&word| Token::new(word)).collect(),
+        };
+
+        let text = tokens.iter().map(Token::form).collect::<Vec<_>>().join(" ");
+        let sentence: Sentence = tokens.into_iter().collect();
+
+        writer.write_sentence(&[format!("# text = {}", text)], &sentence)?;
+    }
+
+    Ok(())
+}
+
+fn convert_raw(
+    tokenizer: &Tokenizer,
+    reader: impl BufRead,
+    writer: &mut SentenceWriter<impl Write>,
+) -> Result<(), Error> {
+    let mut paragraph = String::new();
 
     for line in reader.lines() {
-        let line = line.or_exit("Cannot read sentence", 1);
-        let trimmed = line.trim();
+        let line = line?;
 
-        if trimmed.is_empty() {
+        if line.trim().is_empty() {
+            write_paragraph(tokenizer, &paragraph, writer)?;
+            paragraph.clear();
             continue;
         }
 
-        let sentence: Sentence = trimmed
-            .split(' ')
-            .map(|t| TokenBuilder::new(t).into())
-            .collect();
+        // Normalize whitespace, so that it can be used in '# text' comments.
+        for word in line.split_whitespace() {
+            if !paragraph.is_empty() {
+                paragraph.push(' ');
+            }
+
+            paragraph.push_str(word);
+        }
+    }
 
-        if sentence.len() != 1 {
-            writer
-                .write_sentence(&sentence)
-                .or_exit("Cannot write sentence", 1);
+    write_paragraph(tokenizer, &paragraph, writer)
+}
+
+fn write_paragraph(
+    tokenizer: &Tokenizer,
+    paragraph: &str,
+    writer: &mut SentenceWriter<impl Write>,
+) -> Result<(), Error> {
+    let tokens = tokenizer.tokenize(paragraph);
+    let sentences = tokenizer.split_sentences(paragraph, tokens);
+
+    for (idx, tokens) in sentences.into_iter().enumerate() {
+        let text = &paragraph[tokens[0].start..tokens[tokens.len() - 1].end];
+
+        let mut comments = Vec::new();
+        if idx == 0 {
+            comments.push("# newpar".to_owned());
         }
+        comments.push(format!("# text = {}", text));
+
+        let sentence: Sentence = tokens
+            .into_iter()
+            .map(|token| TokenBuilder::new(&paragraph[token]).into())
+            .collect();
+
+        writer.write_sentence(&comments, &sentence)?;
     }
+
+    Ok(())
 }
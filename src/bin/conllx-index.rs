@@ -0,0 +1,58 @@
+use clap::{App, AppSettings, Arg};
+use conllx_utils::SentenceIndex;
+use stdinout::OrExit;
+
+static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
+    AppSettings::DontCollapseArgsInUsage,
+    AppSettings::UnifiedHelpMessage,
+];
+
+static CORPORA: &str = "CORPORA";
+
+pub struct IndexApp {
+    corpora: Vec<String>,
+}
+
+impl IndexApp {
+    fn new() -> Self {
+        let matches = App::new("conllx-index")
+            .settings(DEFAULT_CLAP_SETTINGS)
+            .arg(
+                Arg::with_name(CORPORA)
+                    .help("Corpora to index")
+                    .required(true)
+                    .min_values(1),
+            )
+            .get_matches();
+
+        let corpora = matches
+            .values_of(CORPORA)
+            .unwrap()
+            .map(ToOwned::to_owned)
+            .collect();
+
+        IndexApp { corpora }
+    }
+}
+
+fn main() {
+    let app = IndexApp::new();
+
+    for corpus in &app.corpora {
+        if corpus.ends_with(".gz") {
+            eprintln!("Cannot index compressed corpus: {}", corpus);
+            std::process::exit(1);
+        }
+
+        let index = SentenceIndex::build_for_file(corpus).or_exit("Cannot index corpus", 1);
+        index
+            .write_for_file(corpus)
+            .or_exit("Cannot write index", 1);
+
+        eprintln!(
+            "{}: {} sentences",
+            SentenceIndex::index_path(corpus).to_string_lossy(),
+            index.len()
+        );
+    }
+}
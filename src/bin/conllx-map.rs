@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter};
+
+use clap::{App, AppSettings, Arg};
+use conllx::{Token, WriteSentence};
+use conllx_utils::{layer_callback, layer_setter, Condition, LayerCallback, LayerSetter};
+use failure::{bail, Error};
+use itertools::Itertools;
+use stdinout::{Input, OrExit, Output};
+
+static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
+    AppSettings::DontCollapseArgsInUsage,
+    AppSettings::UnifiedHelpMessage,
+];
+
+static MAPPING: &str = "MAPPING";
+static INPUT: &str = "INPUT";
+static OUTPUT: &str = "OUTPUT";
+static LAYER: &str = "LAYER";
+static TARGET: &str = "TARGET";
+static UNMAPPED: &str = "UNMAPPED";
+
+/// A mapping rule.
+///
+/// A rule maps a value to another value if all its conditions hold.
+struct Rule {
+    mapped: String,
+    conditions: Vec<Condition>,
+}
+
+/// Mapping table.
+///
+/// Each line of the table consists of tab-separated fields: the value,
+/// the mapped value, and zero or more conditions of the form
+/// `LAYER=VALUE`. The first rule for a value of which all conditions
+/// hold is applied.
+struct Mapping(HashMap<String, Vec<Rule>>);
+
+impl Mapping {
+    fn read(read: impl BufRead) -> Result<Self, Error> {
+        let mut mapping = HashMap::new();
+
+        for line in read.lines() {
+            let line = line?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<_> = line.split('\t').collect();
+            if fields.len() < 2 {
+                bail!("Expected at least 2 fields, got: {}", line);
+            }
+
+            let conditions = fields[2..]
+                .iter()
+                .map(|condition| Condition::parse(condition))
+                .collect::<Result<_, _>>()?;
+
+            mapping
+                .entry(fields[0].to_owned())
+                .or_insert_with(Vec::new)
+                .push(Rule {
+                    mapped: fields[1].to_owned(),
+                    conditions,
+                });
+        }
+
+        Ok(Mapping(mapping))
+    }
+
+    fn map(&self, value: &str, token: &Token) -> Option<&str> {
+        self.0.get(value).and_then(|rules| {
+            rules
+                .iter()
+                .find(|rule| rule.conditions.iter().all(|c| c.matches(token)))
+                .map(|rule| rule.mapped.as_str())
+        })
+    }
+}
+
+pub struct MapApp {
+    mapping: Mapping,
+    input: Option<String>,
+    output: Option<String>,
+    layer_callback: LayerCallback,
+    target_setter: LayerSetter,
+    unmapped: Option<String>,
+}
+
+impl MapApp {
+    fn new() -> Self {
+        let matches = App::new("conllx-map")
+            .settings(DEFAULT_CLAP_SETTINGS)
+            .arg(
+                Arg::with_name(MAPPING)
+                    .help("Mapping table")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(Arg::with_name(INPUT).help("Input data").index(2))
+            .arg(Arg::with_name(OUTPUT).help("Output data").index(3))
+            .arg(
+                Arg::with_name(LAYER)
+                    .short("l")
+                    .long("layer")
+                    .takes_value(true)
+                    .possible_values(&["form", "lemma", "cpos", "pos", "headrel", "pheadrel"])
+                    .default_value("pos")
+                    .help("Layer to map"),
+            )
+            .arg(
+                Arg::with_name(TARGET)
+                    .short("t")
+                    .long("target")
+                    .takes_value(true)
+                    .possible_values(&["form", "lemma", "cpos", "pos", "headrel", "pheadrel"])
+                    .help("Layer to store mapped values in (default: the mapped layer)"),
+            )
+            .arg(
+                Arg::with_name(UNMAPPED)
+                    .short("u")
+                    .long("unmapped")
+                    .takes_value(true)
+                    .help("Replace unmapped values by this value"),
+            )
+            .get_matches();
+
+        let mapping = Mapping::read(BufReader::new(
+            File::open(matches.value_of(MAPPING).unwrap()).or_exit("Cannot open mapping", 1),
+        ))
+        .or_exit("Cannot read mapping", 1);
+
+        let input = matches.value_of(INPUT).map(ToOwned::to_owned);
+        let output = matches.value_of(OUTPUT).map(ToOwned::to_owned);
+        let layer = matches.value_of(LAYER).unwrap();
+        let layer_callback = layer_callback(layer).expect("Unknown layer");
+        let target_setter =
+            layer_setter(matches.value_of(TARGET).unwrap_or(layer)).expect("Unknown layer");
+        let unmapped = matches.value_of(UNMAPPED).map(ToOwned::to_owned);
+
+        MapApp {
+            mapping,
+            input,
+            output,
+            layer_callback,
+            target_setter,
+            unmapped,
+        }
+    }
+}
+
+fn main() {
+    let app = MapApp::new();
+
+    let input = Input::from(app.input.as_ref());
+    let reader = conllx::Reader::new(input.buf_read().or_exit("Cannot open input", 1));
+
+    let output = Output::from(app.output.as_ref());
+    let mut writer = conllx::Writer::new(BufWriter::new(
+        output.write().or_exit("Cannot open output", 1),
+    ));
+
+    let mut unmapped = HashMap::new();
+
+    for sentence in reader {
+        let mut sentence = sentence.or_exit("Cannot read sentence", 1);
+
+        for token in &mut sentence {
+            map_token(&app, token, &mut unmapped).or_exit("Cannot map token", 1);
+        }
+
+        writer
+            .write_sentence(&sentence)
+            .or_exit("Cannot write sentence", 1);
+    }
+
+    if !unmapped.is_empty() {
+        eprintln!("Unmapped values:");
+        for (value, count) in unmapped
+            .into_iter()
+            .sorted_by(|(v1, c1), (v2, c2)| c2.cmp(c1).then_with(|| v1.cmp(v2)))
+        {
+            eprintln!("{}\t{}", value, count);
+        }
+    }
+}
+
+fn map_token(
+    app: &MapApp,
+    token: &mut Token,
+    unmapped: &mut HashMap<String, usize>,
+) -> Result<(), Error> {
+    let value = match (app.layer_callback)(token) {
+        Some(value) => value.into_owned(),
+        None => return Ok(()),
+    };
+
+    match app.mapping.map(&value, token).map(ToOwned::to_owned) {
+        Some(mapped) => (app.target_setter)(token, Some(&mapped))?,
+        None => {
+            *unmapped.entry(value.clone()).or_insert(0) += 1;
+
+            let replacement = app.unmapped.as_ref().unwrap_or(&value);
+            (app.target_setter)(token, Some(replacement))?;
+        }
+    }
+
+    Ok(())
+}
@@ -1,13 +1,18 @@
 use std::env::args;
-use std::io::BufRead;
+use std::process;
 
-use conllx::{PartitioningWriter, WriteSentence};
-use conllx_utils::{open_writer, or_exit};
+use conllx_utils::{open_commented_writer, or_exit, CommentedReader, Unit, Units};
 use getopts::Options;
 use stdinout::Input;
 
 fn print_usage(program: &str, opts: Options) {
-    let brief = format!("Usage: {} N PREFIX SUFFIX [FILE]", program);
+    let brief = format!(
+        "Usage: {} [options] N PREFIX SUFFIX [FILE]\n\n\
+         With --unit, documents (starting at '# newdoc') or paragraphs\n\
+         (starting at '# newpar' or '# newdoc') are partitioned, so that\n\
+         their sentences end up in the same partition.",
+        program
+    );
     print!("{}", opts.usage(&brief));
 }
 
@@ -17,6 +22,12 @@ fn main() {
 
     let mut opts = Options::new();
     opts.optflag("h", "help", "print this help menu");
+    opts.optopt(
+        "u",
+        "unit",
+        "unit to partition: sentence, paragraph, or document (default: sentence)",
+        "UNIT",
+    );
     let matches = or_exit(opts.parse(&args[1..]));
 
     if matches.opt_present("h") {
@@ -30,28 +41,36 @@ fn main() {
     }
 
     let n: usize = or_exit(matches.free[0].parse());
+    if n == 0 {
+        eprintln!("The number of partitions should be at least 1");
+        print_usage(&program, opts);
+        process::exit(1);
+    }
     let prefix = &matches.free[1];
     let suffix = &matches.free[2];
 
+    let unit: Unit = matches
+        .opt_str("u")
+        .map(|unit| or_exit(unit.parse()))
+        .unwrap_or(Unit::Sentence);
+
     let input = Input::from(matches.free.get(3));
-    let reader = conllx::Reader::new(or_exit(input.buf_read()));
+    let reader = CommentedReader::new(or_exit(input.buf_read()));
 
-    let writers: Vec<_> = (0..n)
-        .map(|part| or_exit(open_writer(&format!("{}{}{}", prefix, part, suffix))))
+    let mut writers: Vec<_> = (0..n)
+        .map(|part| {
+            or_exit(open_commented_writer(&format!(
+                "{}{}{}",
+                prefix, part, suffix
+            )))
+        })
         .collect();
 
-    let writer = PartitioningWriter::new(writers);
-
-    copy_sents(reader, writer)
-}
-
-fn copy_sents<R, W>(reader: conllx::Reader<R>, mut writer: W)
-where
-    R: BufRead,
-    W: WriteSentence,
-{
-    for sentence in reader {
-        let sentence = or_exit(sentence);
-        or_exit(writer.write_sentence(&sentence))
+    // Units are distributed over the partitions in a round-robin fashion.
+    for (idx, unit) in Units::new(reader, unit).enumerate() {
+        let writer = &mut writers[idx % n];
+        for sentence in or_exit(unit) {
+            or_exit(writer.write_sentence(&sentence));
+        }
     }
 }
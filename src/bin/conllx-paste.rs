@@ -0,0 +1,206 @@
+use std::io::{BufRead, BufWriter};
+use std::process;
+
+use clap::{App, AppSettings, Arg};
+use conllx::{ReadSentence, Reader, Sentence, WriteSentence};
+use conllx_utils::{layer_callback, layer_setter, open_reader, LayerCallback, LayerSetter};
+use failure::{bail, ensure, Error};
+use stdinout::{OrExit, Output};
+
+static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
+    AppSettings::DontCollapseArgsInUsage,
+    AppSettings::UnifiedHelpMessage,
+];
+
+static BASE: &str = "BASE";
+static ANNOTATIONS: &str = "ANNOTATIONS";
+static LAYERS: &str = "LAYERS";
+static OUTPUT: &str = "OUTPUT";
+
+pub struct PasteApp {
+    base: String,
+    annotations: Vec<String>,
+    layers: Vec<String>,
+    output: Option<String>,
+}
+
+impl PasteApp {
+    fn new() -> Self {
+        let matches = App::new("conllx-paste")
+            .settings(DEFAULT_CLAP_SETTINGS)
+            .arg(
+                Arg::with_name(BASE)
+                    .help("Base corpus")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name(ANNOTATIONS)
+                    .help("Corpora to copy layers from")
+                    .required(true)
+                    .min_values(1)
+                    .index(2),
+            )
+            .arg(
+                Arg::with_name(LAYERS)
+                    .short("l")
+                    .long("layers")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)
+                    .required(true)
+                    .value_name("LAYER[,LAYER]")
+                    .help(
+                        "Layer(s) to copy, given once per annotation corpus (form, lemma, \
+                         cpos, pos, features, head, headrel, phead, or pheadrel)",
+                    ),
+            )
+            .arg(
+                Arg::with_name(OUTPUT)
+                    .short("w")
+                    .takes_value(true)
+                    .help("Write pasted corpus to a file"),
+            )
+            .get_matches();
+
+        let base = matches.value_of(BASE).unwrap().to_owned();
+        let annotations = matches
+            .values_of(ANNOTATIONS)
+            .unwrap()
+            .map(ToOwned::to_owned)
+            .collect();
+        let layers = matches
+            .values_of(LAYERS)
+            .unwrap()
+            .map(ToOwned::to_owned)
+            .collect();
+        let output = matches.value_of(OUTPUT).map(ToOwned::to_owned);
+
+        PasteApp {
+            base,
+            annotations,
+            layers,
+            output,
+        }
+    }
+}
+
+/// Layers that are copied from an annotation corpus.
+struct Annotation {
+    reader: Reader<Box<dyn BufRead>>,
+    filename: String,
+    layers: Vec<(LayerCallback, LayerSetter)>,
+}
+
+fn main() {
+    let app = PasteApp::new();
+
+    if app.annotations.len() != app.layers.len() {
+        eprintln!(
+            "Got {} annotation corpora, but {} layer specifications",
+            app.annotations.len(),
+            app.layers.len()
+        );
+        process::exit(1);
+    }
+
+    let mut annotations = app
+        .annotations
+        .iter()
+        .zip(&app.layers)
+        .map(|(filename, layers)| Annotation {
+            reader: open_reader(filename).or_exit("Cannot open annotation corpus", 1),
+            filename: filename.clone(),
+            layers: layers
+                .split(',')
+                .map(|layer| match (layer_callback(layer), layer_setter(layer)) {
+                    (Some(callback), Some(setter)) => (callback, setter),
+                    _ => {
+                        eprintln!("Unknown layer: {}", layer);
+                        process::exit(1)
+                    }
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+
+    let base = open_reader(&app.base).or_exit("Cannot open base corpus", 1);
+
+    let output = Output::from(app.output);
+    let mut writer = conllx::Writer::new(BufWriter::new(
+        output.write().or_exit("Cannot open output for writing", 1),
+    ));
+
+    paste(base, &mut annotations, &mut writer).or_exit("Cannot paste layers", 1);
+}
+
+fn paste(
+    base: Reader<impl BufRead>,
+    annotations: &mut [Annotation],
+    writer: &mut impl WriteSentence,
+) -> Result<(), Error> {
+    for (sent_idx, sentence) in base.into_iter().enumerate() {
+        let mut sentence = sentence?;
+
+        for annotation in annotations.iter_mut() {
+            let annotated = match annotation.reader.read_sentence()? {
+                Some(annotated) => annotated,
+                None => bail!(
+                    "{} ends before sentence {} of the base corpus",
+                    annotation.filename,
+                    sent_idx + 1
+                ),
+            };
+
+            check_tokenization(&sentence, &annotated, sent_idx, &annotation.filename)?;
+
+            for (base_token, token) in sentence.iter_mut().zip(&annotated) {
+                for (callback, setter) in &annotation.layers {
+                    setter(base_token, callback(token).as_ref().map(AsRef::as_ref))?;
+                }
+            }
+        }
+
+        writer.write_sentence(&sentence)?;
+    }
+
+    for annotation in annotations {
+        ensure!(
+            annotation.reader.read_sentence()?.is_none(),
+            "{} has more sentences than the base corpus",
+            annotation.filename
+        );
+    }
+
+    Ok(())
+}
+
+fn check_tokenization(
+    base: &Sentence,
+    annotated: &Sentence,
+    sent_idx: usize,
+    filename: &str,
+) -> Result<(), Error> {
+    ensure!(
+        base.len() == annotated.len(),
+        "Sentence {}: {} has {} tokens, the base corpus {}",
+        sent_idx + 1,
+        filename,
+        annotated.len(),
+        base.len()
+    );
+
+    for (token_idx, (base_token, token)) in base.iter().zip(annotated).enumerate() {
+        ensure!(
+            base_token.form() == token.form(),
+            "Sentence {}, token {}: {} has form '{}', the base corpus '{}'",
+            sent_idx + 1,
+            token_idx + 1,
+            filename,
+            token.form(),
+            base_token.form()
+        );
+    }
+
+    Ok(())
+}
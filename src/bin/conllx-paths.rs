@@ -0,0 +1,302 @@
+use std::borrow::Cow;
+use std::io::{BufWriter, Write};
+
+use clap::{App, AppSettings, Arg};
+use conllx::Token;
+use conllx_utils::{layer_or_feature_callback, sentence_to_graph, DependencyGraph, LayerCallback};
+use failure::Error;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+use regex::Regex;
+use stdinout::{Input, OrExit, Output};
+
+static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
+    AppSettings::DontCollapseArgsInUsage,
+    AppSettings::UnifiedHelpMessage,
+];
+
+static INPUT: &str = "INPUT";
+static OUTPUT: &str = "OUTPUT";
+static BOTH_DIRECTIONS: &str = "BOTH_DIRECTIONS";
+static CONTENT: &str = "CONTENT";
+static CONTENT_LAYER: &str = "CONTENT_LAYER";
+static LAYER: &str = "LAYER";
+static MAX_LENGTH: &str = "MAX_LENGTH";
+static NODE_LAYER: &str = "NODE_LAYER";
+static PROJECTIVE: &str = "PROJECTIVE";
+static RELATIONS_ONLY: &str = "RELATIONS_ONLY";
+
+pub struct PathsApp {
+    input: Option<String>,
+    output: Option<String>,
+    both_directions: bool,
+    content: Regex,
+    content_callback: LayerCallback,
+    callback: LayerCallback,
+    max_length: usize,
+    node_callback: Option<LayerCallback>,
+    projective: bool,
+}
+
+impl PathsApp {
+    fn new() -> Self {
+        let matches = App::new("conllx-paths")
+            .settings(DEFAULT_CLAP_SETTINGS)
+            .arg(Arg::with_name(INPUT).help("Input data").index(1))
+            .arg(Arg::with_name(OUTPUT).help("Output data").index(2))
+            .arg(
+                Arg::with_name(BOTH_DIRECTIONS)
+                    .short("b")
+                    .long("both-directions")
+                    .help("Extract paths in both directions, rather than left to right"),
+            )
+            .arg(
+                Arg::with_name(CONTENT)
+                    .short("c")
+                    .long("content")
+                    .takes_value(true)
+                    .value_name("REGEX")
+                    .default_value("^(ADJ|ADV|NOUN|PROPN|VERB)$")
+                    .help("Pattern matching the tags of content words"),
+            )
+            .arg(
+                Arg::with_name(CONTENT_LAYER)
+                    .long("content-layer")
+                    .takes_value(true)
+                    .value_name("LAYER")
+                    .default_value("cpos")
+                    .help("Layer (or feature:NAME) that the content pattern is matched against"),
+            )
+            .arg(
+                Arg::with_name(LAYER)
+                    .short("l")
+                    .long("layer")
+                    .takes_value(true)
+                    .default_value("form")
+                    .help("Layer (or feature:NAME) of the words at the ends of paths"),
+            )
+            .arg(
+                Arg::with_name(MAX_LENGTH)
+                    .short("k")
+                    .long("max-length")
+                    .takes_value(true)
+                    .value_name("K")
+                    .default_value("3")
+                    .help("Maximum path length in dependency relations"),
+            )
+            .arg(
+                Arg::with_name(NODE_LAYER)
+                    .short("n")
+                    .long("node-layer")
+                    .takes_value(true)
+                    .value_name("LAYER")
+                    .default_value("cpos")
+                    .help("Layer (or feature:NAME) of the intermediate nodes of paths"),
+            )
+            .arg(
+                Arg::with_name(PROJECTIVE)
+                    .short("p")
+                    .long("projective")
+                    .help("Use the projective (PHEAD/PDEPREL) columns"),
+            )
+            .arg(
+                Arg::with_name(RELATIONS_ONLY)
+                    .short("r")
+                    .long("relations-only")
+                    .help("Leave out the intermediate nodes of paths"),
+            )
+            .get_matches();
+
+        let input = matches.value_of(INPUT).map(ToOwned::to_owned);
+        let output = matches.value_of(OUTPUT).map(ToOwned::to_owned);
+        let both_directions = matches.is_present(BOTH_DIRECTIONS);
+        let content =
+            Regex::new(matches.value_of(CONTENT).unwrap()).or_exit("Cannot compile pattern", 1);
+        let content_callback = callback_or_exit(matches.value_of(CONTENT_LAYER).unwrap());
+        let callback = callback_or_exit(matches.value_of(LAYER).unwrap());
+        let max_length = matches
+            .value_of(MAX_LENGTH)
+            .unwrap()
+            .parse()
+            .or_exit("Cannot parse maximum path length", 1);
+        let node_callback = if matches.is_present(RELATIONS_ONLY) {
+            None
+        } else {
+            Some(callback_or_exit(matches.value_of(NODE_LAYER).unwrap()))
+        };
+        let projective = matches.is_present(PROJECTIVE);
+
+        PathsApp {
+            input,
+            output,
+            both_directions,
+            content,
+            content_callback,
+            callback,
+            max_length,
+            node_callback,
+            projective,
+        }
+    }
+}
+
+fn callback_or_exit(layer: &str) -> LayerCallback {
+    layer_or_feature_callback(layer).or_exit(format!("Unknown layer: {}", layer), 1)
+}
+
+fn layer_value<'a>(callback: &LayerCallback, token: &'a Token) -> Cow<'a, str> {
+    callback(token).unwrap_or(Cow::Borrowed("_"))
+}
+
+/// A step in a dependency path.
+struct Step {
+    /// The node that the step leads to.
+    node: NodeIndex,
+
+    /// The relation that is traversed.
+    rel: String,
+
+    /// Whether the relation is traversed from the dependent to the head.
+    inverse: bool,
+}
+
+fn main() {
+    let app = PathsApp::new();
+
+    let input = Input::from(app.input.as_ref());
+    let reader = conllx::Reader::new(input.buf_read().or_exit("Cannot open input", 1));
+
+    let output = Output::from(app.output.as_ref());
+    let mut writer = BufWriter::new(output.write().or_exit("Cannot open output", 1));
+
+    for sentence in reader {
+        let sentence = sentence.or_exit("Cannot read sentence", 1);
+        write_paths(&app, &sentence, &mut writer).or_exit("Cannot write paths", 1);
+    }
+}
+
+fn write_paths(app: &PathsApp, sentence: &[Token], writer: &mut impl Write) -> Result<(), Error> {
+    let graph = sentence_to_graph(sentence, app.projective);
+
+    let is_content: Vec<_> = sentence
+        .iter()
+        .map(|token| {
+            (app.content_callback)(token)
+                .map(|tag| app.content.is_match(&tag))
+                .unwrap_or(false)
+        })
+        .collect();
+
+    for source in graph.node_indices() {
+        if !is_content[source.index()] {
+            continue;
+        }
+
+        let mut steps = Vec::new();
+        let mut visited = vec![false; sentence.len()];
+        visited[source.index()] = true;
+        extend_paths(
+            app,
+            &graph,
+            &is_content,
+            source,
+            source,
+            &mut steps,
+            &mut visited,
+            writer,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Write all paths starting at `source` that extend the given steps.
+#[allow(clippy::too_many_arguments)]
+fn extend_paths(
+    app: &PathsApp,
+    graph: &DependencyGraph<'_>,
+    is_content: &[bool],
+    source: NodeIndex,
+    node: NodeIndex,
+    steps: &mut Vec<Step>,
+    visited: &mut [bool],
+    writer: &mut impl Write,
+) -> Result<(), Error> {
+    if steps.len() == app.max_length {
+        return Ok(());
+    }
+
+    let heads = graph
+        .edges_directed(node, Direction::Incoming)
+        .map(|edge| (edge.source(), edge.weight(), true));
+    let dependents = graph
+        .edges_directed(node, Direction::Outgoing)
+        .map(|edge| (edge.target(), edge.weight(), false));
+    let neighbors: Vec<_> = heads.chain(dependents).collect();
+
+    for (next, rel, inverse) in neighbors {
+        // Trees have a single path between two nodes, visited nodes can
+        // only be reached again in graphs with cycles.
+        if visited[next.index()] {
+            continue;
+        }
+
+        visited[next.index()] = true;
+        steps.push(Step {
+            node: next,
+            rel: rel.unwrap_or("_").to_owned(),
+            inverse,
+        });
+
+        if is_content[next.index()] && (app.both_directions || next.index() > source.index()) {
+            write_path(app, graph, source, steps, writer)?;
+        }
+
+        extend_paths(app, graph, is_content, source, next, steps, visited, writer)?;
+
+        steps.pop();
+        visited[next.index()] = false;
+    }
+
+    Ok(())
+}
+
+/// Write a path, such as `nsubj^-1 > VERB > obj`.
+fn write_path(
+    app: &PathsApp,
+    graph: &DependencyGraph<'_>,
+    source: NodeIndex,
+    steps: &[Step],
+    writer: &mut impl Write,
+) -> Result<(), Error> {
+    let mut path = String::new();
+
+    for (idx, step) in steps.iter().enumerate() {
+        if idx != 0 {
+            path.push_str(" > ");
+            if let Some(ref node_callback) = app.node_callback {
+                let node = steps[idx - 1].node;
+                path.push_str(&layer_value(node_callback, graph[node].token));
+                path.push_str(" > ");
+            }
+        }
+
+        path.push_str(&step.rel);
+        if step.inverse {
+            path.push_str("^-1");
+        }
+    }
+
+    let target = steps.last().unwrap().node;
+
+    writeln!(
+        writer,
+        "{}\t{}\t{}",
+        layer_value(&app.callback, graph[source].token),
+        path,
+        layer_value(&app.callback, graph[target].token)
+    )?;
+
+    Ok(())
+}
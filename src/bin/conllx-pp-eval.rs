@@ -0,0 +1,328 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::io::BufRead;
+
+use clap::{App, AppSettings, Arg};
+use conllx::{ReadSentence, Sentence, Token};
+use conllx_utils::{
+    first_matching_edge, layer_callback, open_reader, sentence_to_graph, LayerCallback,
+};
+use failure::{bail, ensure, Error};
+use petgraph::graph::node_index;
+use petgraph::EdgeDirection;
+use stdinout::OrExit;
+
+static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
+    AppSettings::DontCollapseArgsInUsage,
+    AppSettings::UnifiedHelpMessage,
+];
+
+static GOLD: &str = "GOLD";
+static SYSTEM: &str = "SYSTEM";
+static DECISIONS: &str = "DECISIONS";
+static HEAD_LAYER: &str = "HEAD_LAYER";
+static OBJECT_RELATION: &str = "OBJECT_RELATION";
+static PREP_LAYER: &str = "PREP_LAYER";
+static RELATION: &str = "RELATION";
+
+pub struct PPEvalApp {
+    gold: String,
+    system: String,
+    decisions: bool,
+    head_layer: String,
+    head_callback: LayerCallback,
+    object_relation: String,
+    prep_layer: String,
+    prep_callback: LayerCallback,
+    relation: String,
+}
+
+impl PPEvalApp {
+    fn new() -> Self {
+        let matches = App::new("conllx-pp-eval")
+            .settings(DEFAULT_CLAP_SETTINGS)
+            .arg(
+                Arg::with_name(GOLD)
+                    .help("Gold standard data")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name(SYSTEM)
+                    .help("System data")
+                    .required(true)
+                    .index(2),
+            )
+            .arg(
+                Arg::with_name(DECISIONS)
+                    .short("d")
+                    .long("decisions")
+                    .help("Print all attachment decisions"),
+            )
+            .arg(
+                Arg::with_name(HEAD_LAYER)
+                    .long("head-layer")
+                    .takes_value(true)
+                    .possible_values(&["form", "lemma", "cpos", "pos"])
+                    .default_value("cpos")
+                    .help("Layer to break down results by head"),
+            )
+            .arg(
+                Arg::with_name(OBJECT_RELATION)
+                    .short("o")
+                    .long("object-relation")
+                    .takes_value(true)
+                    .default_value("PN")
+                    .help("Relation between a preposition and its object"),
+            )
+            .arg(
+                Arg::with_name(PREP_LAYER)
+                    .long("prep-layer")
+                    .takes_value(true)
+                    .possible_values(&["form", "lemma"])
+                    .default_value("lemma")
+                    .help("Layer to break down results by preposition"),
+            )
+            .arg(
+                Arg::with_name(RELATION)
+                    .short("r")
+                    .long("relation")
+                    .takes_value(true)
+                    .default_value("PP")
+                    .help("Relation of prepositional attachments"),
+            )
+            .get_matches();
+
+        let gold = matches.value_of(GOLD).unwrap().to_owned();
+        let system = matches.value_of(SYSTEM).unwrap().to_owned();
+        let decisions = matches.is_present(DECISIONS);
+        let head_layer = matches.value_of(HEAD_LAYER).unwrap().to_owned();
+        let head_callback = layer_callback(&head_layer).expect("Unknown layer");
+        let object_relation = matches.value_of(OBJECT_RELATION).unwrap().to_owned();
+        let prep_layer = matches.value_of(PREP_LAYER).unwrap().to_owned();
+        let prep_callback = layer_callback(&prep_layer).expect("Unknown layer");
+        let relation = matches.value_of(RELATION).unwrap().to_owned();
+
+        PPEvalApp {
+            gold,
+            system,
+            decisions,
+            head_layer,
+            head_callback,
+            object_relation,
+            prep_layer,
+            prep_callback,
+            relation,
+        }
+    }
+}
+
+/// A prepositional attachment decision.
+struct Attachment {
+    preposition: usize,
+    object: Option<usize>,
+    gold_head: usize,
+    system_head: Option<usize>,
+}
+
+impl Attachment {
+    fn is_correct(&self) -> bool {
+        self.system_head == Some(self.gold_head)
+    }
+}
+
+/// Correct and total attachments.
+#[derive(Default)]
+struct Counts {
+    correct: usize,
+    total: usize,
+}
+
+impl Counts {
+    fn add(&mut self, correct: bool) {
+        if correct {
+            self.correct += 1;
+        }
+
+        self.total += 1;
+    }
+
+    fn print(&self, label: &str) {
+        println!(
+            "{}\t{:.2} ({}/{})",
+            label,
+            (100. * self.correct as f64) / self.total as f64,
+            self.correct,
+            self.total
+        );
+    }
+}
+
+#[derive(Default)]
+struct Evaluation {
+    overall: Counts,
+    by_head: BTreeMap<String, Counts>,
+    by_preposition: BTreeMap<String, Counts>,
+}
+
+fn main() {
+    let app = PPEvalApp::new();
+
+    let gold_reader = open_reader(&app.gold).or_exit("Cannot open gold standard data", 1);
+    let system_reader = open_reader(&app.system).or_exit("Cannot open system data", 1);
+
+    let eval = evaluate(&app, gold_reader, system_reader).or_exit("Cannot evaluate", 1);
+
+    if app.decisions {
+        println!();
+    }
+
+    eval.overall.print("Overall");
+
+    println!("\nBy head ({}):", app.head_layer);
+    for (head, counts) in &eval.by_head {
+        counts.print(head);
+    }
+
+    println!("\nBy preposition ({}):", app.prep_layer);
+    for (preposition, counts) in &eval.by_preposition {
+        counts.print(preposition);
+    }
+}
+
+fn evaluate(
+    app: &PPEvalApp,
+    gold_reader: conllx::Reader<impl BufRead>,
+    mut system_reader: conllx::Reader<impl BufRead>,
+) -> Result<Evaluation, Error> {
+    let mut eval = Evaluation::default();
+
+    for (sent_idx, gold) in gold_reader.into_iter().enumerate() {
+        let gold = gold?;
+        let system = match system_reader.read_sentence()? {
+            Some(system) => system,
+            None => bail!(
+                "System data ends before sentence {} of the gold standard",
+                sent_idx + 1
+            ),
+        };
+
+        ensure!(
+            gold.len() == system.len(),
+            "Sentence {}: different number of tokens: {} {}",
+            sent_idx + 1,
+            gold.len(),
+            system.len()
+        );
+
+        check_heads(&gold, sent_idx, "gold standard")?;
+        check_heads(&system, sent_idx, "system")?;
+
+        for attachment in attachments(app, &gold, &system) {
+            let correct = attachment.is_correct();
+            let preposition = &gold[attachment.preposition];
+
+            if app.decisions {
+                print_decision(app, sent_idx, &gold, &attachment);
+            }
+
+            eval.overall.add(correct);
+            eval.by_head
+                .entry(head_label(app, &gold, Some(attachment.gold_head)).into_owned())
+                .or_default()
+                .add(correct);
+            eval.by_preposition
+                .entry(layer_or_form(&app.prep_callback, preposition).into_owned())
+                .or_default()
+                .add(correct);
+        }
+    }
+
+    ensure!(
+        system_reader.read_sentence()?.is_none(),
+        "System data has more sentences than the gold standard"
+    );
+
+    Ok(eval)
+}
+
+/// Check that all heads are within the sentence.
+fn check_heads(sentence: &Sentence, sent_idx: usize, data: &str) -> Result<(), Error> {
+    for (idx, token) in sentence.iter().enumerate() {
+        if let Some(head) = token.head() {
+            ensure!(
+                head <= sentence.len(),
+                "Sentence {}, token {}: {} head {} is beyond the end of the sentence",
+                sent_idx + 1,
+                idx + 1,
+                data,
+                head
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Extract the prepositional attachments from a sentence.
+fn attachments(app: &PPEvalApp, gold: &Sentence, system: &Sentence) -> Vec<Attachment> {
+    let graph = sentence_to_graph(gold, false);
+
+    gold.iter()
+        .enumerate()
+        .filter(|(_, token)| token.head_rel() == Some(app.relation.as_str()))
+        .filter_map(|(idx, token)| {
+            token.head().map(|gold_head| Attachment {
+                preposition: idx,
+                object: first_matching_edge(
+                    &graph,
+                    node_index(idx),
+                    EdgeDirection::Outgoing,
+                    |rel| *rel == Some(app.object_relation.as_str()),
+                )
+                .map(|object| graph[object].offset),
+                gold_head,
+                system_head: system[idx].head(),
+            })
+        })
+        .collect()
+}
+
+fn print_decision(app: &PPEvalApp, sent_idx: usize, gold: &Sentence, attachment: &Attachment) {
+    println!(
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        sent_idx + 1,
+        attachment.preposition + 1,
+        layer_or_form(&app.prep_callback, &gold[attachment.preposition]),
+        attachment
+            .object
+            .map(|object| gold[object].form())
+            .unwrap_or("_"),
+        attachment.gold_head,
+        head_label(app, gold, Some(attachment.gold_head)),
+        attachment
+            .system_head
+            .map(|head| head.to_string())
+            .unwrap_or_else(|| "_".to_owned()),
+        head_label(app, gold, attachment.system_head),
+        if attachment.is_correct() {
+            "correct"
+        } else {
+            "incorrect"
+        }
+    );
+}
+
+/// Label of a head in the breakdown by head.
+fn head_label<'a>(app: &PPEvalApp, sentence: &'a Sentence, head: Option<usize>) -> Cow<'a, str> {
+    match head {
+        Some(0) => Cow::Borrowed("ROOT"),
+        Some(head) => (app.head_callback)(&sentence[head - 1]).unwrap_or(Cow::Borrowed("_")),
+        None => Cow::Borrowed("_"),
+    }
+}
+
+/// Get the value of a layer, falling back to the form.
+fn layer_or_form<'a>(callback: &LayerCallback, token: &'a Token) -> Cow<'a, str> {
+    callback(token).unwrap_or_else(|| Cow::Borrowed(token.form()))
+}
@@ -33,7 +33,7 @@ fn main() {
     let projectivizer = HeadProjectivizer::new();
     let deproj = matches.opt_present("d");
 
-    let input = Input::from(matches.free.get(0));
+    let input = Input::from(matches.free.first());
     let reader = conllx::Reader::new(or_exit(input.buf_read()));
 
     let output = Output::from(matches.free.get(1));
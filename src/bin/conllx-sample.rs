@@ -1,29 +1,114 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
 use std::env::args;
 use std::io::BufWriter;
+use std::process;
 
-use conllx::{ReadSentence, WriteSentence};
-use conllx_utils::or_exit;
+use conllx::{ReadSentence, Sentence, WriteSentence};
+use conllx_utils::{
+    feature_callback, layer_or_feature_callback, or_exit, LayerCallback, SeededRng,
+};
 use getopts::Options;
-use rand;
-use rand::{Rng, SeedableRng, XorShiftRng};
-use reservoir;
 use stdinout::{Input, OrExit, Output};
 
 fn print_usage(program: &str, opts: Options) {
     let brief = format!(
-        "Usage: {} [options] SAMPLE_SIZE [INPUT_FILE] [OUTPUT_FILE]",
+        "Usage: {} [options] SAMPLE_SIZE [INPUT_FILE] [OUTPUT_FILE]\n\n\
+         SAMPLE_SIZE is a number of sentences (or tokens with --tokens) or a\n\
+         fraction such as 0.1. When a fraction is given, every sentence is\n\
+         selected independently with that probability, so the size of the\n\
+         sample is approximate. With stratification, the sample size applies\n\
+         to every stratum.",
         program
     );
     print!("{}", opts.usage(&brief));
 }
 
+/// Size of the sample.
+#[derive(Clone, Copy)]
+enum SampleSize {
+    Count(usize),
+    Fraction(f64),
+}
+
+impl SampleSize {
+    fn parse(size: &str) -> Self {
+        if size.contains('.') {
+            let fraction: f64 = size
+                .parse()
+                .or_exit(format!("Cannot parse '{}' as a fraction", size), 1);
+            if fraction <= 0. || fraction > 1. {
+                eprintln!("Fraction should be in (0, 1]: {}", size);
+                process::exit(1);
+            }
+
+            SampleSize::Fraction(fraction)
+        } else {
+            SampleSize::Count(
+                size.parse()
+                    .or_exit(format!("Cannot parse '{}' as an integer", size), 1),
+            )
+        }
+    }
+}
+
+/// Strata for stratified sampling.
+enum Strata {
+    /// Sentence length bins, given by their inclusive upper bounds.
+    Length(Vec<usize>),
+
+    /// The value of a layer or feature of the first token that has it.
+    Layer(LayerCallback),
+}
+
+impl Strata {
+    fn stratum(&self, sentence: &Sentence) -> String {
+        match self {
+            Strata::Length(bounds) => bounds
+                .iter()
+                .position(|&bound| sentence.len() <= bound)
+                .unwrap_or(bounds.len())
+                .to_string(),
+            Strata::Layer(callback) => sentence
+                .iter()
+                .find_map(callback)
+                .map(|value| value.into_owned())
+                .unwrap_or_default(),
+        }
+    }
+}
+
 fn main() {
     let args: Vec<String> = args().collect();
     let program = args[0].clone();
 
     let mut opts = Options::new();
     opts.optflag("h", "help", "print this help menu");
-    opts.optopt("s", "seed", "RNG seed", "SEED");
+    opts.optopt(
+        "s",
+        "seed",
+        "RNG seed, an arbitrary string (default: random, printed to stderr)",
+        "SEED",
+    );
+    opts.optopt(
+        "",
+        "stratify-length",
+        "stratify by sentence length, using comma-separated upper bounds of the length bins",
+        "BOUNDS",
+    );
+    opts.optopt(
+        "",
+        "stratify-layer",
+        "stratify by the value of a layer (or feature:NAME) of the first token that has it",
+        "LAYER",
+    );
+    opts.optflag("t", "tokens", "the sample size is a number of tokens");
+    opts.optopt(
+        "w",
+        "weight",
+        "weigh sentences by a feature of the first token that has it (default weight: 1)",
+        "FEATURE",
+    );
     let matches = or_exit(opts.parse(&args[1..]));
 
     if matches.opt_present("h") {
@@ -36,7 +121,23 @@ fn main() {
         return;
     }
 
-    let sample_size = or_exit(matches.free[0].parse());
+    let sample_size = SampleSize::parse(&matches.free[0]);
+    let tokens = matches.opt_present("t");
+    let weight_callback = matches.opt_str("w").map(feature_callback);
+    let strata = match (
+        matches.opt_str("stratify-length"),
+        matches.opt_str("stratify-layer"),
+    ) {
+        (Some(_), Some(_)) => {
+            eprintln!("Sentences can only be stratified by length or by a layer");
+            process::exit(1);
+        }
+        (Some(bounds), None) => Some(Strata::Length(parse_bounds(&bounds))),
+        (None, Some(layer)) => Some(Strata::Layer(
+            layer_or_feature_callback(&layer).or_exit(format!("Unknown layer: {}", layer), 1),
+        )),
+        (None, None) => None,
+    };
 
     let input = Input::from(matches.free.get(1));
     let reader = conllx::Reader::new(or_exit(input.buf_read()));
@@ -44,20 +145,207 @@ fn main() {
     let output = Output::from(matches.free.get(2));
     let mut writer = conllx::Writer::new(BufWriter::new(or_exit(output.write())));
 
-    let seed = if let Some(seed_str) = matches.opt_str("s") {
-        let seed_val: u32 = seed_str
+    let mut rng = SeededRng::from_seed_or_random(matches.opt_str("s").as_deref());
+
+    let sample_size = match sample_size {
+        SampleSize::Count(sample_size) => sample_size,
+        SampleSize::Fraction(fraction) => {
+            if weight_callback.is_some() || strata.is_some() {
+                eprintln!("Weighted and stratified sampling require a sample size, not a fraction");
+                process::exit(1);
+            }
+
+            for sentence in reader.sentences() {
+                let sentence = or_exit(sentence);
+                if rng.next_f64() < fraction {
+                    or_exit(writer.write_sentence(&sentence));
+                }
+            }
+
+            return;
+        }
+    };
+
+    if !tokens && weight_callback.is_none() && strata.is_none() {
+        let sample = reservoir_sample(&mut rng, sample_size, reader.sentences().map(or_exit));
+
+        for sentence in sample {
+            or_exit(writer.write_sentence(&sentence));
+        }
+
+        return;
+    }
+
+    let mut samplers = HashMap::new();
+    for (idx, sentence) in reader.sentences().enumerate() {
+        let sentence = or_exit(sentence);
+
+        let weight = weight_callback
+            .as_ref()
+            .map(|callback| sentence_weight(callback, &sentence))
+            .unwrap_or(1.);
+        if weight == 0. {
+            continue;
+        }
+
+        let key = (1. - rng.next_f64()).powf(1. / weight);
+        let cost = if tokens { sentence.len() } else { 1 };
+        let stratum = strata
+            .as_ref()
+            .map(|strata| strata.stratum(&sentence))
+            .unwrap_or_default();
+
+        samplers
+            .entry(stratum)
+            .or_insert_with(|| KeySampler::new(sample_size))
+            .add(Candidate {
+                key,
+                idx,
+                cost,
+                sentence,
+            });
+    }
+
+    let mut sample: Vec<_> = samplers
+        .into_values()
+        .flat_map(KeySampler::into_candidates)
+        .collect();
+    sample.sort_by_key(|candidate| candidate.idx);
+
+    for candidate in sample {
+        or_exit(writer.write_sentence(&candidate.sentence));
+    }
+}
+
+/// Uniform reservoir sampling (Vitter's algorithm R).
+fn reservoir_sample<T>(
+    rng: &mut SeededRng,
+    sample_size: usize,
+    iter: impl Iterator<Item = T>,
+) -> Vec<T> {
+    let mut sample = Vec::with_capacity(sample_size);
+
+    for (idx, item) in iter.enumerate() {
+        if idx < sample_size {
+            sample.push(item);
+        } else {
+            let replace = rng.index(idx + 1);
+            if replace < sample_size {
+                sample[replace] = item;
+            }
+        }
+    }
+
+    sample
+}
+
+fn parse_bounds(bounds: &str) -> Vec<usize> {
+    let mut bounds: Vec<usize> = bounds
+        .split(',')
+        .map(|bound| {
+            bound
+                .trim()
+                .parse()
+                .or_exit(format!("Cannot parse length bound: {}", bound), 1)
+        })
+        .collect();
+    bounds.sort_unstable();
+    bounds
+}
+
+fn sentence_weight(callback: &LayerCallback, sentence: &Sentence) -> f64 {
+    let weight = match sentence.iter().find_map(callback) {
+        Some(weight) => weight
             .parse()
-            .or_exit(format!("Cannot not parse '{}' as an integer", seed_str), 1);
-        [seed_val; 4]
-    } else {
-        rand::thread_rng().gen()
+            .or_exit(format!("Cannot parse weight: {}", weight), 1),
+        None => return 1.,
     };
 
-    let mut rng = XorShiftRng::from_seed(seed);
+    if weight < 0. {
+        eprintln!("Weights cannot be negative: {}", weight);
+        process::exit(1);
+    }
+
+    weight
+}
+
+/// A sentence that is a candidate for the sample.
+struct Candidate {
+    key: f64,
+    idx: usize,
+    cost: usize,
+    sentence: Sentence,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    // Reversed, such that the candidate with the smallest key is at the
+    // top of the heap.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .key
+            .partial_cmp(&self.key)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.idx.cmp(&self.idx))
+    }
+}
+
+/// Streaming sampler that keeps the candidates with the largest keys.
+///
+/// Every candidate has the random key u^(1/w), where u is drawn uniformly
+/// from (0, 1] and w is the weight of the candidate (Efraimidis & Spirakis,
+/// 2006). The sample consists of the candidates with the largest keys whose
+/// total cost fits in the budget. The cost of a candidate is 1 when sampling
+/// sentences and the sentence length when sampling tokens, so that only
+/// the sample is kept in memory.
+struct KeySampler {
+    budget: usize,
+    used: usize,
+    threshold: f64,
+    candidates: BinaryHeap<Candidate>,
+}
+
+impl KeySampler {
+    fn new(budget: usize) -> Self {
+        KeySampler {
+            budget,
+            used: 0,
+            threshold: 0.,
+            candidates: BinaryHeap::new(),
+        }
+    }
+
+    fn add(&mut self, candidate: Candidate) {
+        // Candidates with a key below that of an evicted candidate would
+        // have been evicted as well.
+        if candidate.key <= self.threshold || candidate.cost > self.budget {
+            return;
+        }
 
-    let sample = reservoir::sample(&mut rng, sample_size, reader.sentences().map(or_exit));
+        self.used += candidate.cost;
+        self.candidates.push(candidate);
+
+        while self.used > self.budget {
+            let evicted = self.candidates.pop().unwrap();
+            self.used -= evicted.cost;
+            self.threshold = self.threshold.max(evicted.key);
+        }
+    }
 
-    for sentence in sample {
-        or_exit(writer.write_sentence(&sentence));
+    fn into_candidates(self) -> Vec<Candidate> {
+        self.candidates.into_vec()
     }
 }
@@ -3,19 +3,28 @@ extern crate conllx_utils;
 extern crate getopts;
 extern crate stdinout;
 
+use std::collections::{HashMap, HashSet};
 use std::env::args;
 use std::fs::File;
-use std::io::{BufRead, BufReader, BufWriter};
+use std::hash::Hash;
+use std::io::{BufRead, BufReader, BufWriter, Write};
 use std::process;
 
-use conllx::WriteSentence;
-use conllx_utils::or_exit;
+use conllx_utils::{or_exit, CommentedReader, CommentedSentence, CommentedWriter, IndexedReader};
+use failure::{bail, Error};
 use getopts::Options;
-use stdinout::{Input, Output};
+use stdinout::{Input, OrExit, Output};
 
 fn print_usage(program: &str, opts: Options) {
     let brief = format!(
-        "Usage: {} [options] ID_FILE [INPUT_FILE] [OUTPUT_FILE]",
+        "Usage: {} [options] ID_FILE [INPUT_FILE] [OUTPUT_FILE]\n\n\
+         Every line of ID_FILE is a (0-based) sentence number or an inclusive\n\
+         range such as 100-200. With --sent-id, every line is a sentence\n\
+         identifier from a '# sent_id' comment.\n\n\
+         Sentences are written in the order of ID_FILE, unless --corpus-order\n\
+         or --exclude is used. In the ID_FILE order, sentences are read using\n\
+         the index INPUT_FILE.idx when it is present and up to date (see\n\
+         conllx-index).",
         program
     );
     print!("{}", opts.usage(&brief));
@@ -26,7 +35,14 @@ fn main() {
     let program = args[0].clone();
 
     let mut opts = Options::new();
+    opts.optflag(
+        "c",
+        "corpus-order",
+        "write sentences in corpus order, streaming the corpus",
+    );
     opts.optflag("h", "help", "print this help menu");
+    opts.optflag("i", "sent-id", "select sentences by their sent_id comment");
+    opts.optflag("x", "exclude", "write the sentences that are not selected");
     let matches = or_exit(opts.parse(&args[1..]));
 
     if matches.opt_present("h") {
@@ -39,18 +55,181 @@ fn main() {
         process::exit(1);
     }
 
-    let ids = BufReader::new(or_exit(File::open(&matches.free[0])))
+    let corpus_order = matches.opt_present("c") || matches.opt_present("x");
+    let exclude = matches.opt_present("x");
+
+    let lines = BufReader::new(or_exit(File::open(&matches.free[0])))
         .lines()
         .map(or_exit)
-        .map(|s| or_exit(s.parse::<usize>()))
-        .collect::<Vec<_>>();
+        .map(|line| line.trim().to_owned())
+        .filter(|line| !line.is_empty());
 
-    let input = Input::from(matches.free.get(1));
-    let reader = conllx::Reader::new(or_exit(input.buf_read()));
-    let corpus = reader.into_iter().map(or_exit).collect::<Vec<_>>();
     let output = Output::from(matches.free.get(2));
-    let mut writer = conllx::Writer::new(BufWriter::new(or_exit(output.write())));
-    for id in ids {
-        or_exit(writer.write_sentence(&corpus[id]));
+    let mut writer = CommentedWriter::new(BufWriter::new(or_exit(output.write())));
+
+    if matches.opt_present("i") {
+        let ids = lines.collect::<Vec<_>>();
+        let input = Input::from(matches.free.get(1));
+        let reader = CommentedReader::new(or_exit(input.buf_read()));
+        let selection = Selection::new(&ids, corpus_order, exclude);
+        selection
+            .select(reader, &mut writer, |_, sentence| {
+                sentence.sent_id().map(ToOwned::to_owned)
+            })
+            .or_exit("Cannot select sentences", 1);
+        return;
+    }
+
+    let mut ids = Vec::new();
+    for line in lines {
+        let (start, end) = parse_range(&line);
+        ids.extend(start..=end);
+    }
+
+    match matches.free.get(1) {
+        // Files are accessed through an index, so that the corpus does not
+        // have to be loaded into memory.
+        Some(path) if !corpus_order => {
+            let mut reader = or_exit(IndexedReader::open(path));
+            let n_sentences = reader.index().len();
+            if let Some(id) = ids.iter().find(|&&id| id >= n_sentences) {
+                eprintln!("{}", out_of_range(*id, n_sentences));
+                process::exit(1);
+            }
+
+            for id in ids {
+                let sentence = or_exit(reader.sentence(id))
+                    .or_exit(format!("Corpus does not have sentence {}", id), 1);
+                or_exit(writer.write_sentence(&sentence));
+            }
+        }
+        path => {
+            let input = Input::from(path);
+            let reader = CommentedReader::new(or_exit(input.buf_read()));
+            let selection = Selection::new(&ids, corpus_order, exclude);
+            selection
+                .select(reader, &mut writer, |idx, _| Some(idx))
+                .or_exit("Cannot select sentences", 1);
+        }
+    }
+}
+
+fn out_of_range(id: usize, n_sentences: usize) -> String {
+    format!(
+        "Sentence {} is out of range, the corpus has {} sentences (numbered from 0)",
+        id, n_sentences
+    )
+}
+
+/// Parse a sentence number or an inclusive range of sentence numbers.
+fn parse_range(range: &str) -> (usize, usize) {
+    let mut parts = range.splitn(2, '-');
+    let start: usize = parts.next().unwrap().trim().parse().or_exit(
+        format!("Cannot parse sentence number or range: {}", range),
+        1,
+    );
+    let end: usize = parts
+        .next()
+        .map(|end| {
+            end.trim().parse().or_exit(
+                format!("Cannot parse sentence number or range: {}", range),
+                1,
+            )
+        })
+        .unwrap_or(start);
+
+    if end < start {
+        eprintln!("Invalid range: {}", range);
+        process::exit(1);
+    }
+
+    (start, end)
+}
+
+/// Selection of sentences from a corpus that is read sequentially.
+struct Selection<'a, K> {
+    ids: &'a [K],
+    corpus_order: bool,
+    exclude: bool,
+}
+
+impl<'a, K> Selection<'a, K>
+where
+    K: Eq + Hash + SelectionKey,
+{
+    fn new(ids: &'a [K], corpus_order: bool, exclude: bool) -> Self {
+        Selection {
+            ids,
+            corpus_order,
+            exclude,
+        }
+    }
+
+    /// Select sentences, using `key` to get the selection key of a sentence.
+    ///
+    /// In corpus order, sentences are written as they are read. Otherwise,
+    /// only the selected sentences are kept in memory until the corpus is
+    /// read completely.
+    fn select<R, W, F>(
+        &self,
+        reader: CommentedReader<R>,
+        writer: &mut CommentedWriter<W>,
+        key: F,
+    ) -> Result<(), Error>
+    where
+        R: BufRead,
+        W: Write,
+        F: Fn(usize, &CommentedSentence) -> Option<K>,
+    {
+        let selected: HashSet<&K> = self.ids.iter().collect();
+        let mut found = HashMap::new();
+        let mut n_sentences = 0;
+
+        for (idx, sentence) in reader.enumerate() {
+            let sentence = sentence?;
+            n_sentences += 1;
+
+            let key = key(idx, &sentence).filter(|key| selected.contains(key));
+
+            if self.corpus_order {
+                if key.is_some() != self.exclude {
+                    writer.write_sentence(&sentence)?;
+                }
+
+                if let Some(key) = key {
+                    found.insert(key, None);
+                }
+            } else if let Some(key) = key {
+                found.insert(key, Some(sentence));
+            }
+        }
+
+        for id in self.ids {
+            match found.get(id) {
+                Some(Some(sentence)) => writer.write_sentence(sentence)?,
+                Some(None) => (),
+                None => bail!("{}", id.missing(n_sentences)),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Keys that can be used to select sentences.
+trait SelectionKey {
+    /// Error message for a key that is not in the corpus.
+    fn missing(&self, n_sentences: usize) -> String;
+}
+
+impl SelectionKey for usize {
+    fn missing(&self, n_sentences: usize) -> String {
+        out_of_range(*self, n_sentences)
+    }
+}
+
+impl SelectionKey for String {
+    fn missing(&self, _n_sentences: usize) -> String {
+        format!("Corpus does not have a sentence with identifier: {}", self)
     }
 }
@@ -1,15 +1,24 @@
 use std::env::args;
 use std::io::BufWriter;
+use std::process;
 
-use conllx::WriteSentence;
-use conllx_utils::or_exit;
+use conllx_utils::{
+    external_shuffle, or_exit, CommentedReader, CommentedWriter, SeededRng, Unit, Units,
+};
 use getopts::Options;
-use rand;
-use rand::{Rng, SeedableRng, XorShiftRng};
 use stdinout::{Input, OrExit, Output};
 
 fn print_usage(program: &str, opts: Options) {
-    let brief = format!("Usage: {} [options] [INPUT_FILE] [OUTPUT_FILE]", program);
+    let brief = format!(
+        "Usage: {} [options] [INPUT_FILE] [OUTPUT_FILE]\n\n\
+         With --buckets, sentences are shuffled using temporary bucket files,\n\
+         so that only one bucket is kept in memory. The bucket files are\n\
+         stored in the directory given by TMPDIR (default: /tmp).\n\n\
+         With --unit, documents (starting at '# newdoc') or paragraphs\n\
+         (starting at '# newpar' or '# newdoc') are shuffled, preserving the\n\
+         order of the sentences within them.",
+        program
+    );
     print!("{}", opts.usage(&brief));
 }
 
@@ -18,8 +27,25 @@ fn main() {
     let program = args[0].clone();
 
     let mut opts = Options::new();
+    opts.optopt(
+        "b",
+        "buckets",
+        "shuffle on disk using N temporary buckets",
+        "N",
+    );
     opts.optflag("h", "help", "print this help menu");
-    opts.optopt("s", "seed", "RNG seed", "SEED");
+    opts.optopt(
+        "s",
+        "seed",
+        "RNG seed, an arbitrary string (default: random, printed to stderr)",
+        "SEED",
+    );
+    opts.optopt(
+        "u",
+        "unit",
+        "unit to shuffle: sentence, paragraph, or document (default: sentence)",
+        "UNIT",
+    );
     let matches = or_exit(opts.parse(&args[1..]));
 
     if matches.opt_present("h") {
@@ -32,33 +58,46 @@ fn main() {
         return;
     }
 
-    let seed = if let Some(seed_str) = matches.opt_str("s") {
-        let seed_val: u32 = seed_str
+    let mut rng = SeededRng::from_seed_or_random(matches.opt_str("s").as_deref());
+
+    let buckets = matches.opt_str("b").map(|buckets| {
+        let buckets: usize = buckets
             .parse()
-            .or_exit(format!("Cannot not parse '{}' as an integer", seed_str), 1);
-        [seed_val; 4]
-    } else {
-        rand::thread_rng().gen()
-    };
+            .or_exit(format!("Cannot parse '{}' as an integer", buckets), 1);
+        if buckets == 0 {
+            eprintln!("The number of buckets should be at least 1");
+            process::exit(1);
+        }
+        buckets
+    });
 
-    let mut rng = XorShiftRng::from_seed(seed);
+    let unit: Unit = matches
+        .opt_str("u")
+        .map(|unit| unit.parse().or_exit("Cannot parse unit", 1))
+        .unwrap_or(Unit::Sentence);
 
-    let input = Input::from(matches.free.get(0));
-    let reader = conllx::Reader::new(or_exit(input.buf_read()));
+    let input = Input::from(matches.free.first());
+    let reader = CommentedReader::new(or_exit(input.buf_read()));
+    let units = Units::new(reader, unit);
 
     let output = Output::from(matches.free.get(1));
-    let mut writer = conllx::Writer::new(BufWriter::new(or_exit(output.write())));
+    let mut writer = CommentedWriter::new(BufWriter::new(or_exit(output.write())));
+
+    if let Some(buckets) = buckets {
+        external_shuffle(&mut rng, units, buckets, &mut writer)
+            .or_exit("Cannot shuffle sentences", 1);
+        return;
+    }
 
-    let mut sents: Vec<_> = reader
-        .into_iter()
+    let mut units: Vec<_> = units
         .map(|r| r.or_exit("Cannot read sentence", 1))
         .collect();
 
-    rng.shuffle(&mut sents);
+    rng.shuffle(&mut units);
 
-    for sent in sents {
+    for sent in units.iter().flatten() {
         writer
-            .write_sentence(&sent)
+            .write_sentence(sent)
             .or_exit("Cannot write sentence", 1);
     }
 }
@@ -0,0 +1,199 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::iter;
+use std::process;
+
+use clap::{App, AppSettings, Arg};
+use conllx_utils::{open_commented_writer, CommentedReader, SeededRng, Unit, Units};
+use failure::Error;
+use stdinout::OrExit;
+
+static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
+    AppSettings::DontCollapseArgsInUsage,
+    AppSettings::UnifiedHelpMessage,
+];
+
+static PROPORTIONS: &str = "PROPORTIONS";
+static PREFIX: &str = "PREFIX";
+static SUFFIX: &str = "SUFFIX";
+static INPUT: &str = "INPUT";
+static SEED: &str = "SEED";
+static UNIT: &str = "UNIT";
+
+pub struct SplitApp {
+    proportions: Vec<f64>,
+    prefix: String,
+    suffix: String,
+    input: String,
+    seed: Option<String>,
+    unit: Unit,
+}
+
+impl SplitApp {
+    fn new() -> Self {
+        let matches = App::new("conllx-split")
+            .settings(DEFAULT_CLAP_SETTINGS)
+            .about(
+                "Randomly split a corpus into parts of the given proportions. \
+                 The parts are written to PREFIX0SUFFIX, PREFIX1SUFFIX, etc.",
+            )
+            .arg(
+                Arg::with_name(PROPORTIONS)
+                    .help("Comma-separated proportions of the parts, e.g. 80,10,10")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(
+                Arg::with_name(PREFIX)
+                    .help("Prefix of the output files")
+                    .required(true)
+                    .index(2),
+            )
+            .arg(
+                Arg::with_name(SUFFIX)
+                    .help("Suffix of the output files")
+                    .required(true)
+                    .index(3),
+            )
+            .arg(
+                Arg::with_name(INPUT)
+                    .help("Input corpus (it is read twice)")
+                    .required(true)
+                    .index(4),
+            )
+            .arg(
+                Arg::with_name(SEED)
+                    .short("s")
+                    .long("seed")
+                    .takes_value(true)
+                    .help("RNG seed, an arbitrary string (default: random, printed to stderr)"),
+            )
+            .arg(
+                Arg::with_name(UNIT)
+                    .short("u")
+                    .long("unit")
+                    .takes_value(true)
+                    .possible_values(&["sentence", "paragraph", "document"])
+                    .default_value("sentence")
+                    .help(
+                        "Unit to split: sentences, paragraphs (starting at '# newpar' \
+                         or '# newdoc'), or documents (starting at '# newdoc')",
+                    ),
+            )
+            .get_matches();
+
+        let proportions: Vec<f64> = matches
+            .value_of(PROPORTIONS)
+            .unwrap()
+            .split(',')
+            .map(|p| {
+                p.trim()
+                    .parse()
+                    .or_exit(format!("Cannot parse proportion: {}", p), 1)
+            })
+            .collect();
+        let total: f64 = proportions.iter().sum();
+        if proportions.iter().any(|&p| p < 0.) || !total.is_finite() || total <= 0. {
+            eprintln!("Proportions should be finite, non-negative, and not all zero");
+            process::exit(1);
+        }
+
+        let prefix = matches.value_of(PREFIX).unwrap().to_owned();
+        let suffix = matches.value_of(SUFFIX).unwrap().to_owned();
+        let input = matches.value_of(INPUT).unwrap().to_owned();
+        let seed = matches.value_of(SEED).map(ToOwned::to_owned);
+        let unit = matches
+            .value_of(UNIT)
+            .unwrap()
+            .parse()
+            .or_exit("Cannot parse unit", 1);
+
+        SplitApp {
+            proportions,
+            prefix,
+            suffix,
+            input,
+            seed,
+            unit,
+        }
+    }
+}
+
+fn main() {
+    let app = SplitApp::new();
+
+    let mut rng = SeededRng::from_seed_or_random(app.seed.as_deref());
+
+    let n_units = count_units(&app).or_exit("Cannot read corpus", 1);
+    let sizes = part_sizes(&app.proportions, n_units);
+
+    // Assign units to parts by shuffling the part labels.
+    let mut parts: Vec<usize> = sizes
+        .iter()
+        .enumerate()
+        .flat_map(|(part, &size)| iter::repeat_n(part, size))
+        .collect();
+    rng.shuffle(&mut parts);
+
+    let mut writers: Vec<_> = (0..sizes.len())
+        .map(|part| {
+            open_commented_writer(&format!("{}{}{}", app.prefix, part, app.suffix))
+                .or_exit("Cannot open output file", 1)
+        })
+        .collect();
+
+    let reader = CommentedReader::new(BufReader::new(
+        File::open(&app.input).or_exit("Cannot open corpus", 1),
+    ));
+    for (unit, part) in Units::new(reader, app.unit).zip(parts) {
+        for sentence in unit.or_exit("Cannot read sentence", 1) {
+            writers[part]
+                .write_sentence(&sentence)
+                .or_exit("Cannot write sentence", 1);
+        }
+    }
+
+    for (part, size) in sizes.iter().enumerate() {
+        eprintln!("{}{}{}: {} units", app.prefix, part, app.suffix, size);
+    }
+}
+
+fn count_units(app: &SplitApp) -> Result<usize, Error> {
+    let reader = CommentedReader::new(BufReader::new(File::open(&app.input)?));
+
+    let mut n_units = 0;
+    for unit in Units::new(reader, app.unit) {
+        unit?;
+        n_units += 1;
+    }
+
+    Ok(n_units)
+}
+
+/// Compute the number of units in each part.
+///
+/// Units that remain after rounding down are assigned to the parts with
+/// the largest remainders.
+fn part_sizes(proportions: &[f64], n_units: usize) -> Vec<usize> {
+    let total: f64 = proportions.iter().sum();
+    let exact: Vec<f64> = proportions
+        .iter()
+        .map(|p| p / total * n_units as f64)
+        .collect();
+
+    let mut sizes: Vec<usize> = exact.iter().map(|size| size.floor() as usize).collect();
+
+    let mut by_remainder: Vec<usize> = (0..sizes.len()).collect();
+    by_remainder.sort_by(|&a, &b| {
+        (exact[b] - exact[b].floor())
+            .partial_cmp(&(exact[a] - exact[a].floor()))
+            .unwrap()
+    });
+
+    let remaining = n_units - sizes.iter().sum::<usize>();
+    for &part in by_remainder.iter().take(remaining) {
+        sizes[part] += 1;
+    }
+
+    sizes
+}
@@ -1,9 +1,12 @@
-use std::borrow::Cow;
 use std::io::{BufWriter, Write};
 
 use clap::{App, AppSettings, Arg};
-use conllx_utils::layer_ng::{layer_callback, LayerCallback};
-use itertools::Itertools;
+use conllx::Token;
+use conllx_utils::{
+    feature_callback, layer_or_feature_callback, CommentedReader, CommentedWriter, Detokenizer,
+    Language, LayerCallback,
+};
+use failure::{bail, format_err, Error};
 use stdinout::{Input, OrExit, Output};
 
 static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
@@ -13,20 +16,52 @@ static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
 
 static INPUT: &str = "INPUT";
 static OUTPUT: &str = "OUTPUT";
+static DETOKENIZE: &str = "DETOKENIZE";
+static IDS: &str = "IDS";
+static LANGUAGE: &str = "LANGUAGE";
 static LAYER: &str = "LAYER";
+static SENTENCE_SEPARATOR: &str = "SENTENCE_SEPARATOR";
+static TEMPLATE: &str = "TEMPLATE";
+static TEXT_COMMENTS: &str = "TEXT_COMMENTS";
+static TOKEN_SEPARATOR: &str = "TOKEN_SEPARATOR";
 
 pub struct ToTextApp {
     input: Option<String>,
     output: Option<String>,
-    layer_callback: LayerCallback,
+    detokenizer: Option<Detokenizer>,
+    ids: bool,
+    sentence_separator: String,
+    template: Template,
+    text_comments: bool,
+    token_separator: String,
 }
 
 impl ToTextApp {
     fn new() -> Self {
-        let matches = App::new("conllx-from-text")
+        let matches = App::new("conllx-to-text")
             .settings(DEFAULT_CLAP_SETTINGS)
             .arg(Arg::with_name(INPUT).help("Input data").index(1))
             .arg(Arg::with_name(OUTPUT).help("Output data").index(2))
+            .arg(
+                Arg::with_name(DETOKENIZE)
+                    .short("d")
+                    .long("detokenize")
+                    .help("Detokenize using SpaceAfter=No or punctuation rules"),
+            )
+            .arg(
+                Arg::with_name(IDS)
+                    .short("i")
+                    .long("ids")
+                    .help("Print the sentence identifier (sent_id or number) before sentences"),
+            )
+            .arg(
+                Arg::with_name(LANGUAGE)
+                    .long("language")
+                    .takes_value(true)
+                    .possible_values(&["de", "en", "fr"])
+                    .default_value("en")
+                    .help("Language of the punctuation rules for detokenization"),
+            )
             .arg(
                 Arg::with_name(LAYER)
                     .short("l")
@@ -34,17 +69,147 @@ impl ToTextApp {
                     .default_value("form")
                     .help("Layer to output as text"),
             )
+            .arg(
+                Arg::with_name(SENTENCE_SEPARATOR)
+                    .long("sentence-separator")
+                    .takes_value(true)
+                    .value_name("SEP")
+                    .default_value("\\n")
+                    .help("Separator between sentences"),
+            )
+            .arg(
+                Arg::with_name(TEMPLATE)
+                    .short("t")
+                    .long("template")
+                    .takes_value(true)
+                    .help(
+                        "Token template, such as {form}/{pos} or {form}|{lemma}|{feature:case}, \
+                         overrides -l",
+                    ),
+            )
+            .arg(
+                Arg::with_name(TEXT_COMMENTS)
+                    .long("text-comments")
+                    .help("Write the input with regenerated '# text' comments, implies -d"),
+            )
+            .arg(
+                Arg::with_name(TOKEN_SEPARATOR)
+                    .long("token-separator")
+                    .takes_value(true)
+                    .value_name("SEP")
+                    .default_value(" ")
+                    .help("Separator between tokens"),
+            )
             .get_matches();
 
         let input = matches.value_of(INPUT).map(ToOwned::to_owned);
         let output = matches.value_of(OUTPUT).map(ToOwned::to_owned);
-        let layer_callback =
-            layer_callback(matches.value_of(LAYER).unwrap()).expect("Unknown layer");
+        let text_comments = matches.is_present(TEXT_COMMENTS);
+        let detokenizer = if matches.is_present(DETOKENIZE) || text_comments {
+            let language: Language = matches.value_of(LANGUAGE).unwrap().parse().unwrap();
+            Some(Detokenizer::new(language))
+        } else {
+            None
+        };
+        let ids = matches.is_present(IDS);
+        let sentence_separator = unescape(matches.value_of(SENTENCE_SEPARATOR).unwrap());
+        let template = match matches.value_of(TEMPLATE) {
+            Some(template) => unescape(template),
+            None => format!("{{{}}}", matches.value_of(LAYER).unwrap()),
+        };
+        let template = Template::parse(&template).or_exit("Cannot parse template", 1);
+        let token_separator = unescape(matches.value_of(TOKEN_SEPARATOR).unwrap());
 
         ToTextApp {
             input,
             output,
-            layer_callback,
+            detokenizer,
+            ids,
+            sentence_separator,
+            template,
+            text_comments,
+            token_separator,
+        }
+    }
+}
+
+/// Replace the escape sequences `\t`, `\n`, and `\\`.
+fn unescape(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+enum TemplatePart {
+    Literal(String),
+    Layer(LayerCallback),
+}
+
+/// Template for the text of a token.
+///
+/// Layers are given in braces, e.g. `{form}` or `{feature:case}`. Absent
+/// layers are written as `_`.
+struct Template {
+    parts: Vec<TemplatePart>,
+}
+
+impl Template {
+    fn parse(template: &str) -> Result<Self, Error> {
+        let mut parts = Vec::new();
+
+        let mut rest = template;
+        while let Some(open) = rest.find('{') {
+            if open != 0 {
+                parts.push(TemplatePart::Literal(rest[..open].to_owned()));
+            }
+
+            let close = match rest[open..].find('}') {
+                Some(close) => open + close,
+                None => bail!("Unclosed brace in template: {}", template),
+            };
+
+            let layer = &rest[open + 1..close];
+            // feat:NAME is accepted as an alias of feature:NAME.
+            let callback = match layer.strip_prefix("feat:") {
+                Some(feature) => feature_callback(feature),
+                None => layer_or_feature_callback(layer)
+                    .ok_or_else(|| format_err!("Unknown layer: {}", layer))?,
+            };
+            parts.push(TemplatePart::Layer(callback));
+
+            rest = &rest[close + 1..];
+        }
+
+        if !rest.is_empty() {
+            parts.push(TemplatePart::Literal(rest.to_owned()));
+        }
+
+        Ok(Template { parts })
+    }
+
+    fn render(&self, token: &Token, out: &mut String) {
+        for part in &self.parts {
+            match part {
+                TemplatePart::Literal(literal) => out.push_str(literal),
+                TemplatePart::Layer(callback) => {
+                    out.push_str(callback(token).as_ref().map(AsRef::as_ref).unwrap_or("_"))
+                }
+            }
         }
     }
 }
@@ -52,26 +217,62 @@ impl ToTextApp {
 fn main() {
     let app = ToTextApp::new();
 
-    let input = Input::from(app.input);
-    let reader = conllx_ng::io::Reader::new(input.buf_read().or_exit("Cannot open input", 1));
+    let input = Input::from(app.input.as_ref());
+    let reader = CommentedReader::new(input.buf_read().or_exit("Cannot open input", 1));
 
-    let output = Output::from(app.output);
+    let output = Output::from(app.output.as_ref());
     let mut writer = BufWriter::new(output.write().or_exit("Cannot open output", 1));
 
-    let callback = app.layer_callback;
-    for sentence in reader {
+    if app.text_comments {
+        let detokenizer = app.detokenizer.as_ref().unwrap();
+        let mut writer = CommentedWriter::new(writer);
+        for sentence in reader {
+            let mut sentence = sentence.or_exit("Cannot read sentence", 1);
+            let text = detokenizer.detokenize(&sentence.sentence);
+            sentence.set_comment_value("text", &text);
+            writer
+                .write_sentence(&sentence)
+                .or_exit("Cannot write sentence", 1);
+        }
+
+        return;
+    }
+
+    let mut line = String::new();
+    for (sent_idx, sentence) in reader.enumerate() {
         let sentence = sentence.or_exit("Cannot read sentence", 1);
 
-        writeln!(
-            writer,
-            "{}",
-            sentence
-                .iter()
-                .filter_map(|n| n.token().map(|t| callback(t)
-                    .map(Cow::into_owned)
-                    .unwrap_or_else(|| "_".to_owned())))
-                .join(" ")
-        )
-        .or_exit("Cannot write sentence", 1);
+        line.clear();
+
+        if app.ids {
+            match sentence.sent_id() {
+                Some(id) => line.push_str(id),
+                None => line.push_str(&(sent_idx + 1).to_string()),
+            }
+            line.push('\t');
+        }
+
+        let spaces = app
+            .detokenizer
+            .as_ref()
+            .map(|detokenizer| detokenizer.spaces(&sentence.sentence));
+
+        for (idx, token) in sentence.sentence.iter().enumerate() {
+            if idx != 0 {
+                match spaces {
+                    Some(ref spaces) if !spaces[idx - 1] => (),
+                    Some(_) => line.push(' '),
+                    None => line.push_str(&app.token_separator),
+                }
+            }
+
+            app.template.render(token, &mut line);
+        }
+
+        line.push_str(&app.sentence_separator);
+
+        writer
+            .write_all(line.as_bytes())
+            .or_exit("Cannot write sentence", 1);
     }
 }
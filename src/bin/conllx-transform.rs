@@ -0,0 +1,321 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter};
+
+use clap::{App, AppSettings, Arg};
+use conllx::{Sentence, Token, WriteSentence};
+use conllx_utils::{sentence_to_graph, Condition};
+use failure::{bail, Error};
+use petgraph::visit::EdgeRef;
+use stdinout::{Input, OrExit, Output};
+
+static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
+    AppSettings::DontCollapseArgsInUsage,
+    AppSettings::UnifiedHelpMessage,
+];
+
+static RULES: &str = "RULES";
+static INPUT: &str = "INPUT";
+static OUTPUT: &str = "OUTPUT";
+static PROJECTIVE: &str = "PROJECTIVE";
+
+fn parse_conditions(conditions: &str) -> Result<Vec<Condition>, Error> {
+    if conditions == "*" {
+        return Ok(Vec::new());
+    }
+
+    conditions.split(',').map(Condition::parse).collect()
+}
+
+/// Transformation of a matched edge.
+enum Action {
+    /// Change the relation of the edge.
+    Relabel(String),
+
+    /// Make the dependent the head of its head.
+    ///
+    /// The dependent takes the place of the head in the tree. The former
+    /// head is attached to the dependent, optionally with a new relation.
+    Reverse(Option<String>),
+
+    /// Attach the dependent to the head of its head, optionally with a
+    /// new relation.
+    ///
+    /// Edges of which the head is attached to the root are not raised,
+    /// since that would add a root to the tree.
+    Raise(Option<String>),
+}
+
+impl Action {
+    fn parse(action: &str, argument: Option<&str>) -> Result<Self, Error> {
+        match (action, argument) {
+            ("relabel", Some(relation)) => Ok(Action::Relabel(relation.to_owned())),
+            ("relabel", None) => bail!("The relabel action requires a relation"),
+            ("reverse", relation) => Ok(Action::Reverse(relation.map(ToOwned::to_owned))),
+            ("raise", relation) => Ok(Action::Raise(relation.map(ToOwned::to_owned))),
+            _ => bail!("Unknown action: {}", action),
+        }
+    }
+}
+
+/// A transformation rule.
+///
+/// A rule matches an edge using conditions on the head, the relation, and
+/// the dependent. The action of the rule is applied to each matching edge.
+struct Rule {
+    head: Vec<Condition>,
+    relation: Option<String>,
+    dependent: Vec<Condition>,
+    action: Action,
+}
+
+impl Rule {
+    /// Read transformation rules.
+    ///
+    /// Each line consists of tab-separated fields: the head conditions,
+    /// the relation, the dependent conditions, the action and an optional
+    /// argument of the action. Conditions are comma-separated and have
+    /// the form `LAYER=VALUE`. `*` matches any head, relation or dependent.
+    fn read_rules(read: impl BufRead) -> Result<Vec<Rule>, Error> {
+        let mut rules = Vec::new();
+
+        for line in read.lines() {
+            let line = line?;
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<_> = line.split('\t').collect();
+            if fields.len() < 4 || fields.len() > 5 {
+                bail!("Expected 4 or 5 fields, got: {}", line);
+            }
+
+            let relation = match fields[1] {
+                "*" => None,
+                relation => Some(relation.to_owned()),
+            };
+
+            rules.push(Rule {
+                head: parse_conditions(fields[0])?,
+                relation,
+                dependent: parse_conditions(fields[2])?,
+                action: Action::parse(fields[3], fields.get(4).cloned())?,
+            });
+        }
+
+        Ok(rules)
+    }
+
+    fn matches(&self, head: &Token, relation: Option<&str>, dependent: &Token) -> bool {
+        if let Some(ref rule_relation) = self.relation {
+            if relation != Some(rule_relation.as_str()) {
+                return false;
+            }
+        }
+
+        self.head.iter().all(|c| c.matches(head))
+            && self.dependent.iter().all(|c| c.matches(dependent))
+    }
+}
+
+pub struct TransformApp {
+    rules: Vec<Rule>,
+    input: Option<String>,
+    output: Option<String>,
+    projective: bool,
+}
+
+impl TransformApp {
+    fn new() -> Self {
+        let matches = App::new("conllx-transform")
+            .settings(DEFAULT_CLAP_SETTINGS)
+            .arg(
+                Arg::with_name(RULES)
+                    .help("Transformation rules")
+                    .required(true)
+                    .index(1),
+            )
+            .arg(Arg::with_name(INPUT).help("Input data").index(2))
+            .arg(Arg::with_name(OUTPUT).help("Output data").index(3))
+            .arg(
+                Arg::with_name(PROJECTIVE)
+                    .short("p")
+                    .long("projective")
+                    .help("Transform the projective columns"),
+            )
+            .get_matches();
+
+        let rules = Rule::read_rules(BufReader::new(
+            File::open(matches.value_of(RULES).unwrap()).or_exit("Cannot open rules", 1),
+        ))
+        .or_exit("Cannot read rules", 1);
+
+        let input = matches.value_of(INPUT).map(ToOwned::to_owned);
+        let output = matches.value_of(OUTPUT).map(ToOwned::to_owned);
+        let projective = matches.is_present(PROJECTIVE);
+
+        TransformApp {
+            rules,
+            input,
+            output,
+            projective,
+        }
+    }
+}
+
+fn main() {
+    let app = TransformApp::new();
+
+    let input = Input::from(app.input.as_ref());
+    let reader = conllx::Reader::new(input.buf_read().or_exit("Cannot open input", 1));
+
+    let output = Output::from(app.output.as_ref());
+    let mut writer = conllx::Writer::new(BufWriter::new(
+        output.write().or_exit("Cannot open output", 1),
+    ));
+
+    let mut applications = vec![Applications::default(); app.rules.len()];
+
+    for sentence in reader {
+        let mut sentence = sentence.or_exit("Cannot read sentence", 1);
+
+        for (rule, applications) in app.rules.iter().zip(&mut applications) {
+            apply_rule(&mut sentence, rule, app.projective, applications);
+        }
+
+        writer
+            .write_sentence(&sentence)
+            .or_exit("Cannot write sentence", 1);
+    }
+
+    for (idx, applications) in applications.into_iter().enumerate() {
+        if applications.skipped == 0 {
+            eprintln!(
+                "Rule {}: {} edge(s) transformed",
+                idx + 1,
+                applications.transformed
+            );
+        } else {
+            eprintln!(
+                "Rule {}: {} edge(s) transformed, {} skipped (head is attached to the root)",
+                idx + 1,
+                applications.transformed,
+                applications.skipped
+            );
+        }
+    }
+}
+
+/// Number of transformed and skipped edges of a rule.
+#[derive(Clone, Copy, Default)]
+struct Applications {
+    transformed: usize,
+    skipped: usize,
+}
+
+/// Apply a rule to a sentence.
+fn apply_rule(
+    sentence: &mut Sentence,
+    rule: &Rule,
+    projective: bool,
+    applications: &mut Applications,
+) {
+    let edges: Vec<_> = {
+        let graph = sentence_to_graph(sentence, projective);
+        graph
+            .edge_references()
+            .filter(|edge| {
+                rule.matches(
+                    graph[edge.source()].token,
+                    *edge.weight(),
+                    graph[edge.target()].token,
+                )
+            })
+            .map(|edge| (graph[edge.source()].offset, graph[edge.target()].offset))
+            .collect()
+    };
+
+    for (head, dependent) in edges {
+        // An earlier transformation could have changed the edge.
+        if head_of(&sentence[dependent], projective) != Some(head + 1) {
+            continue;
+        }
+
+        if apply_action(sentence, &rule.action, head, dependent, projective) {
+            applications.transformed += 1;
+        } else {
+            applications.skipped += 1;
+        }
+    }
+}
+
+/// Apply an action to an edge, returning `false` if the edge is skipped.
+fn apply_action(
+    sentence: &mut Sentence,
+    action: &Action,
+    head: usize,
+    dependent: usize,
+    projective: bool,
+) -> bool {
+    match action {
+        Action::Relabel(relation) => {
+            set_head_rel(&mut sentence[dependent], Some(relation.clone()), projective);
+        }
+        Action::Reverse(relation) => {
+            let grandparent = head_of(&sentence[head], projective);
+            let head_relation = head_rel_of(&sentence[head], projective).map(ToOwned::to_owned);
+            let relation = relation
+                .clone()
+                .or_else(|| head_rel_of(&sentence[dependent], projective).map(ToOwned::to_owned));
+
+            set_head(&mut sentence[dependent], grandparent, projective);
+            set_head_rel(&mut sentence[dependent], head_relation, projective);
+            set_head(&mut sentence[head], Some(dependent + 1), projective);
+            set_head_rel(&mut sentence[head], relation, projective);
+        }
+        Action::Raise(relation) => {
+            let grandparent = match head_of(&sentence[head], projective) {
+                Some(0) | None => return false,
+                Some(grandparent) => grandparent,
+            };
+
+            set_head(&mut sentence[dependent], Some(grandparent), projective);
+            if let Some(relation) = relation {
+                set_head_rel(&mut sentence[dependent], Some(relation.clone()), projective);
+            }
+        }
+    }
+
+    true
+}
+
+fn head_of(token: &Token, projective: bool) -> Option<usize> {
+    if projective {
+        token.p_head()
+    } else {
+        token.head()
+    }
+}
+
+fn head_rel_of(token: &Token, projective: bool) -> Option<&str> {
+    if projective {
+        token.p_head_rel()
+    } else {
+        token.head_rel()
+    }
+}
+
+fn set_head(token: &mut Token, head: Option<usize>, projective: bool) {
+    if projective {
+        token.set_p_head(head);
+    } else {
+        token.set_head(head);
+    }
+}
+
+fn set_head_rel(token: &mut Token, head_rel: Option<String>, projective: bool) {
+    if projective {
+        token.set_p_head_rel(head_rel);
+    } else {
+        token.set_head_rel(head_rel);
+    }
+}
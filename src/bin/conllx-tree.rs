@@ -0,0 +1,391 @@
+use std::borrow::Cow;
+use std::process;
+
+use clap::{App, AppSettings, Arg};
+use colored::*;
+use conllx::Sentence;
+use conllx_utils::{layer_callback, CommentedReader, CommentedSentence, LayerCallback};
+use stdinout::{Input, OrExit};
+
+static DEFAULT_CLAP_SETTINGS: &[AppSettings] = &[
+    AppSettings::DontCollapseArgsInUsage,
+    AppSettings::UnifiedHelpMessage,
+];
+
+static INPUT: &str = "INPUT";
+static ASCII: &str = "ASCII";
+static ID: &str = "ID";
+static NO_COLOR: &str = "NO_COLOR";
+static PROJECTIVE: &str = "PROJECTIVE";
+static RANGE: &str = "RANGE";
+static SHOW: &str = "SHOW";
+
+pub struct TreeApp {
+    input: Option<String>,
+    ascii: bool,
+    id: Option<String>,
+    projective: bool,
+    range: Option<(usize, usize)>,
+    show_callbacks: Vec<LayerCallback>,
+}
+
+impl TreeApp {
+    fn new() -> Self {
+        let matches = App::new("conllx-tree")
+            .settings(DEFAULT_CLAP_SETTINGS)
+            .arg(Arg::with_name(INPUT).help("Input data").index(1))
+            .arg(
+                Arg::with_name(ASCII)
+                    .short("a")
+                    .long("ascii")
+                    .help("Draw trees using ASCII characters"),
+            )
+            .arg(
+                Arg::with_name(ID)
+                    .short("i")
+                    .long("id")
+                    .takes_value(true)
+                    .conflicts_with(RANGE)
+                    .help("Show the sentence with this identifier (sent_id comment)"),
+            )
+            .arg(
+                Arg::with_name(NO_COLOR)
+                    .long("no-color")
+                    .help("Do not use colors"),
+            )
+            .arg(
+                Arg::with_name(PROJECTIVE)
+                    .short("p")
+                    .long("projective")
+                    .help("Use the projective columns"),
+            )
+            .arg(
+                Arg::with_name(RANGE)
+                    .short("r")
+                    .long("range")
+                    .takes_value(true)
+                    .value_name("N[-M]")
+                    .help("Show sentence N or sentences N to M (1-based, inclusive)"),
+            )
+            .arg(
+                Arg::with_name(SHOW)
+                    .short("s")
+                    .long("show")
+                    .takes_value(true)
+                    .value_name("LAYER[,LAYER]")
+                    .default_value("form,headrel")
+                    .help(
+                        "Layer(s) to show below the tree (form, lemma, cpos, pos, features, \
+                         head, headrel, phead, or pheadrel)",
+                    ),
+            )
+            .get_matches();
+
+        if matches.is_present(NO_COLOR) {
+            colored::control::set_override(false);
+        }
+
+        let input = matches.value_of(INPUT).map(ToOwned::to_owned);
+        let ascii = matches.is_present(ASCII);
+        let id = matches.value_of(ID).map(ToOwned::to_owned);
+        let projective = matches.is_present(PROJECTIVE);
+        let range = matches.value_of(RANGE).map(parse_range);
+        let show_callbacks = matches
+            .value_of(SHOW)
+            .unwrap()
+            .split(',')
+            .map(|layer| layer_callback(layer).or_exit(format!("Unknown layer: {}", layer), 1))
+            .collect();
+
+        TreeApp {
+            input,
+            ascii,
+            id,
+            projective,
+            range,
+            show_callbacks,
+        }
+    }
+}
+
+fn parse_range(range: &str) -> (usize, usize) {
+    let mut parts = range.splitn(2, '-');
+    let start: usize = parts
+        .next()
+        .unwrap()
+        .parse()
+        .or_exit(format!("Cannot parse range: {}", range), 1);
+    let end: usize = parts
+        .next()
+        .map(|end| {
+            end.parse()
+                .or_exit(format!("Cannot parse range: {}", range), 1)
+        })
+        .unwrap_or(start);
+
+    if start == 0 || end < start {
+        eprintln!("Invalid range: {}", range);
+        process::exit(1);
+    }
+
+    (start, end)
+}
+
+fn main() {
+    let app = TreeApp::new();
+
+    let input = Input::from(app.input.as_ref());
+    let reader = CommentedReader::new(input.buf_read().or_exit("Cannot open input", 1));
+
+    let mut found = false;
+    for (sent_idx, sentence) in reader.enumerate() {
+        let sentence = sentence.or_exit("Cannot read sentence", 1);
+
+        if let Some((start, end)) = app.range {
+            if sent_idx + 1 < start {
+                continue;
+            } else if sent_idx + 1 > end {
+                break;
+            }
+        }
+
+        if let Some(ref id) = app.id {
+            if sentence.sent_id() != Some(id.as_str()) {
+                continue;
+            }
+        }
+
+        if found {
+            println!();
+        }
+
+        print_sentence(&app, sent_idx, &sentence);
+        found = true;
+
+        if app.id.is_some() {
+            break;
+        }
+    }
+
+    if !found {
+        eprintln!("No matching sentences");
+        process::exit(1);
+    }
+}
+
+fn print_sentence(app: &TreeApp, sent_idx: usize, sentence: &CommentedSentence) {
+    match sentence.sent_id() {
+        Some(id) => println!("{}", format!("Sentence {} ({})", sent_idx + 1, id).bold()),
+        None => println!("{}", format!("Sentence {}", sent_idx + 1).bold()),
+    }
+
+    let rows: Vec<Vec<String>> = app
+        .show_callbacks
+        .iter()
+        .map(|callback| {
+            sentence
+                .sentence
+                .iter()
+                .map(|token| callback(token).unwrap_or(Cow::Borrowed("_")).into_owned())
+                .collect()
+        })
+        .collect();
+
+    let widths: Vec<usize> = (0..sentence.sentence.len())
+        .map(|idx| {
+            rows.iter()
+                .map(|row| row[idx].chars().count())
+                .max()
+                .unwrap_or(1)
+                .max(1)
+        })
+        .collect();
+
+    let mut starts = Vec::with_capacity(widths.len());
+    let mut start = 0;
+    for width in &widths {
+        starts.push(start);
+        start += width + 1;
+    }
+
+    // Labels are centred in their columns, so that arcs end on the labels.
+    let centers: Vec<usize> = starts
+        .iter()
+        .zip(&widths)
+        .map(|(start, width)| start + (width - 1) / 2)
+        .collect();
+
+    let canvas = draw_tree(&sentence.sentence, &centers, start, app.projective);
+    for line in canvas.render(app.ascii) {
+        println!("{}", line.trim_end());
+    }
+
+    for (row_idx, row) in rows.iter().enumerate() {
+        let line = row
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| {
+                let padded = format!("{:^width$}", value, width = width);
+                if row_idx == 0 {
+                    padded.bold().to_string()
+                } else {
+                    padded.blue().to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        println!("{}", line.trim_end());
+    }
+}
+
+// Cell flags.
+const UP: u8 = 1;
+const DOWN: u8 = 2;
+const LEFT: u8 = 4;
+const RIGHT: u8 = 8;
+const ARROW: u8 = 16;
+
+/// Canvas on which the lines of a tree are drawn.
+///
+/// Every cell records in which directions lines leave the cell, so that
+/// crossing and joining lines can be drawn with the right characters.
+struct Canvas {
+    cells: Vec<Vec<u8>>,
+}
+
+impl Canvas {
+    fn new(height: usize, width: usize) -> Self {
+        Canvas {
+            cells: vec![vec![0; width]; height],
+        }
+    }
+
+    fn hline(&mut self, row: usize, from: usize, to: usize) {
+        for col in from..=to {
+            if col != from {
+                self.cells[row][col] |= LEFT;
+            }
+
+            if col != to {
+                self.cells[row][col] |= RIGHT;
+            }
+        }
+    }
+
+    fn vline(&mut self, col: usize, from: usize, to: usize) {
+        for row in from..=to {
+            if row != from {
+                self.cells[row][col] |= UP;
+            }
+
+            self.cells[row][col] |= DOWN;
+        }
+    }
+
+    fn arrow(&mut self, row: usize, col: usize) {
+        self.cells[row][col] |= ARROW;
+    }
+
+    fn render(&self, ascii: bool) -> Vec<String> {
+        self.cells
+            .iter()
+            .map(|row| row.iter().map(|&cell| cell_char(cell, ascii)).collect())
+            .collect()
+    }
+}
+
+fn cell_char(cell: u8, ascii: bool) -> char {
+    if cell & ARROW != 0 {
+        return if ascii { 'v' } else { '▼' };
+    }
+
+    let c = match cell {
+        0 => ' ',
+        c if c == LEFT | RIGHT || c == LEFT || c == RIGHT => '─',
+        c if c == UP | DOWN || c == UP || c == DOWN => '│',
+        c if c == DOWN | RIGHT => '┌',
+        c if c == DOWN | LEFT => '┐',
+        c if c == UP | RIGHT => '└',
+        c if c == UP | LEFT => '┘',
+        c if c == UP | DOWN | RIGHT => '├',
+        c if c == UP | DOWN | LEFT => '┤',
+        c if c == DOWN | LEFT | RIGHT => '┬',
+        c if c == UP | LEFT | RIGHT => '┴',
+        _ => '┼',
+    };
+
+    if !ascii {
+        return c;
+    }
+
+    match c {
+        ' ' => ' ',
+        '─' => '-',
+        '│' => '|',
+        _ => '+',
+    }
+}
+
+/// Draw the dependency arcs of a sentence.
+///
+/// Each arc is assigned a level, such that arcs on the same level do not
+/// overlap. Every level uses two rows: one for the horizontal line of the
+/// arc and one for the arrow pointing to the dependent. Root attachments
+/// are marked with an arrow in the first row.
+fn draw_tree(sentence: &Sentence, centers: &[usize], width: usize, projective: bool) -> Canvas {
+    let mut arcs = Vec::new();
+    let mut roots = Vec::new();
+    for (idx, token) in sentence.iter().enumerate() {
+        let head = if projective {
+            token.p_head()
+        } else {
+            token.head()
+        };
+
+        match head {
+            Some(0) => roots.push(idx),
+            Some(head) if head <= sentence.len() && head != idx + 1 => arcs.push((head - 1, idx)),
+            _ => (),
+        }
+    }
+
+    arcs.sort_by_key(|&(head, dep)| (head.max(dep) - head.min(dep), head.min(dep)));
+
+    let mut levels: Vec<Vec<(usize, usize)>> = Vec::new();
+    let mut arc_levels = Vec::with_capacity(arcs.len());
+    for &(head, dep) in &arcs {
+        let (left, right) = (centers[head.min(dep)], centers[head.max(dep)]);
+
+        let level = levels
+            .iter()
+            .position(|spans| spans.iter().all(|&(l, r)| r < left || l > right))
+            .unwrap_or_else(|| {
+                levels.push(Vec::new());
+                levels.len() - 1
+            });
+
+        levels[level].push((left, right));
+        arc_levels.push(level);
+    }
+
+    let height = 1 + 2 * levels.len();
+    let mut canvas = Canvas::new(height, width.max(1));
+
+    for &root in &roots {
+        canvas.vline(centers[root], 0, height - 1);
+        canvas.arrow(0, centers[root]);
+    }
+
+    for (&(head, dep), &level) in arcs.iter().zip(&arc_levels) {
+        // The highest level is drawn in the first rows.
+        let row = 1 + 2 * (levels.len() - 1 - level);
+        let (left, right) = (centers[head.min(dep)], centers[head.max(dep)]);
+
+        canvas.hline(row, left, right);
+        canvas.vline(left, row, height - 1);
+        canvas.vline(right, row, height - 1);
+        canvas.arrow(row + 1, centers[dep]);
+    }
+
+    canvas
+}
@@ -1,29 +1,127 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::env::args;
 use std::io::{BufWriter, Write};
 
+use conllx::Token;
+use conllx_utils::{layer_or_feature_callback, LayerCallback};
 use getopts::Options;
 use stdinout::{Input, OrExit, Output};
 
-macro_rules! ok_or {
-    ($expr:expr, $ok_expr:expr) => {
-        match $expr {
-            Some(val) => val,
-            None => $ok_expr,
-        }
-    };
-}
-
 fn print_usage(program: &str, opts: Options) {
     let brief = format!("Usage: {} [options] [INPUT_FILE] [OUTPUT_FILE]", program);
     print!("{}", opts.usage(&brief));
 }
 
+/// Frequencies of (head, relation, dependent) triples.
+#[derive(Default)]
+struct TripleCounts {
+    triples: HashMap<(String, String, String), usize>,
+    head_rel: HashMap<(String, String), usize>,
+    rel_dep: HashMap<(String, String), usize>,
+    rel: HashMap<String, usize>,
+}
+
+impl TripleCounts {
+    fn add(&mut self, head: &str, rel: &str, dep: &str) {
+        *self
+            .triples
+            .entry((head.to_owned(), rel.to_owned(), dep.to_owned()))
+            .or_insert(0) += 1;
+        *self
+            .head_rel
+            .entry((head.to_owned(), rel.to_owned()))
+            .or_insert(0) += 1;
+        *self
+            .rel_dep
+            .entry((rel.to_owned(), dep.to_owned()))
+            .or_insert(0) += 1;
+        *self.rel.entry(rel.to_owned()).or_insert(0) += 1;
+    }
+
+    /// Get the contingency table of a triple.
+    ///
+    /// The association between the head and the dependent is measured
+    /// among the triples with the same relation.
+    fn contingency(&self, head: &str, rel: &str, dep: &str, count: usize) -> [f64; 4] {
+        let head_rel = self.head_rel[&(head.to_owned(), rel.to_owned())];
+        let rel_dep = self.rel_dep[&(rel.to_owned(), dep.to_owned())];
+        let rel = self.rel[rel];
+
+        [
+            count as f64,
+            (head_rel - count) as f64,
+            (rel_dep - count) as f64,
+            (rel + count - head_rel - rel_dep) as f64,
+        ]
+    }
+}
+
+/// Pointwise mutual information (in bits) of a contingency table.
+fn pmi(table: [f64; 4]) -> f64 {
+    let [k11, k12, k21, k22] = table;
+    let n = k11 + k12 + k21 + k22;
+    (k11 * n / ((k11 + k12) * (k11 + k21))).log2()
+}
+
+/// Log-likelihood ratio (G²) of a contingency table (Dunning, 1993).
+fn log_likelihood(table: [f64; 4]) -> f64 {
+    let [k11, k12, k21, k22] = table;
+    let n = k11 + k12 + k21 + k22;
+
+    let observed_expected = [
+        (k11, (k11 + k12) * (k11 + k21) / n),
+        (k12, (k11 + k12) * (k12 + k22) / n),
+        (k21, (k21 + k22) * (k11 + k21) / n),
+        (k22, (k21 + k22) * (k12 + k22) / n),
+    ];
+
+    2. * observed_expected
+        .iter()
+        .filter(|&&(observed, _)| observed > 0.)
+        .map(|&(observed, expected)| observed * (observed / expected).ln())
+        .sum::<f64>()
+}
+
+fn layer_value<'a>(callback: &LayerCallback, token: &'a Token) -> Cow<'a, str> {
+    callback(token).unwrap_or(Cow::Borrowed("_"))
+}
+
 fn main() {
     let args: Vec<String> = args().collect();
     let program = args[0].clone();
 
     let mut opts = Options::new();
     opts.optflag("h", "help", "print this help menu");
+    opts.optflag(
+        "a",
+        "aggregate",
+        "count unique (head, relation, dependent) triples",
+    );
+    opts.optopt(
+        "",
+        "dep-layer",
+        "layer (or feature:NAME) of dependents (default: form)",
+        "LAYER",
+    );
+    opts.optopt(
+        "",
+        "head-layer",
+        "layer (or feature:NAME) of heads (default: form)",
+        "LAYER",
+    );
+    opts.optflag(
+        "",
+        "llr",
+        "add the log-likelihood ratio of head and dependent given the relation, implies -a",
+    );
+    opts.optflag(
+        "",
+        "pmi",
+        "add the PMI of head and dependent given the relation, implies -a",
+    );
+    opts.optflag("p", "projective", "use the projective columns");
+    opts.optflag("r", "no-root", "omit attachments to the root");
     let matches = opts.parse(&args[1..]).or_exit("Cannot process options", 1);
 
     if matches.opt_present("h") {
@@ -36,35 +134,105 @@ fn main() {
         return;
     }
 
-    let input = Input::from(matches.free.get(0));
+    let head_layer = matches
+        .opt_str("head-layer")
+        .unwrap_or_else(|| "form".into());
+    let head_callback =
+        layer_or_feature_callback(&head_layer).or_exit(format!("Unknown layer: {}", head_layer), 1);
+    let dep_layer = matches
+        .opt_str("dep-layer")
+        .unwrap_or_else(|| "form".into());
+    let dep_callback =
+        layer_or_feature_callback(&dep_layer).or_exit(format!("Unknown layer: {}", dep_layer), 1);
+
+    let with_pmi = matches.opt_present("pmi");
+    let with_llr = matches.opt_present("llr");
+    let aggregate = matches.opt_present("a") || with_pmi || with_llr;
+    let projective = matches.opt_present("p");
+    let no_root = matches.opt_present("r");
+
+    let input = Input::from(matches.free.first());
     let reader = conllx::Reader::new(input.buf_read().or_exit("Cannot open input", 1));
 
     let output = Output::from(matches.free.get(1));
     let mut writer = BufWriter::new(output.write().or_exit("Cannot open output", 1));
 
+    let mut counts = TripleCounts::default();
+    let mut skipped = 0;
+
     for sentence in reader {
         let sentence = sentence.or_exit("Cannot read sentence", 1);
 
         for (idx, dep) in sentence.iter().enumerate() {
-            let head = ok_or!(dep.head(), continue);
-            let head_rel = ok_or!(dep.head_rel(), continue);
+            let (head, head_rel) = if projective {
+                (dep.p_head(), dep.p_head_rel())
+            } else {
+                (dep.head(), dep.head_rel())
+            };
+
+            let (head, head_rel) = match (head, head_rel) {
+                (Some(head), Some(head_rel)) => (head, head_rel),
+                _ => {
+                    skipped += 1;
+                    continue;
+                }
+            };
+
+            if no_root && head == 0 {
+                continue;
+            }
 
-            let head_form = if head == 0 {
-                "ROOT"
+            let head_value = if head == 0 {
+                Cow::Borrowed("ROOT")
             } else {
-                sentence[head - 1].form()
+                layer_value(&head_callback, &sentence[head - 1])
             };
+            let dep_value = layer_value(&dep_callback, dep);
+
+            if aggregate {
+                counts.add(&head_value, head_rel, &dep_value);
+                continue;
+            }
 
             writeln!(
                 writer,
                 "{}\t{}\t{}\t{}\t{}",
                 head,
-                head_form,
+                head_value,
                 head_rel,
                 idx + 1,
-                dep.form()
+                dep_value
             )
             .or_exit("Cannot write triple", 1);
         }
     }
+
+    if skipped != 0 {
+        eprintln!("Skipped {} token(s) without a head or relation", skipped);
+    }
+
+    if !aggregate {
+        return;
+    }
+
+    let mut triples: Vec<_> = counts.triples.iter().collect();
+    triples.sort_by(|(triple, count), (other_triple, other_count)| {
+        other_count
+            .cmp(count)
+            .then_with(|| triple.cmp(other_triple))
+    });
+
+    for ((head, rel, dep), &count) in triples {
+        write!(writer, "{}\t{}\t{}\t{}", head, rel, dep, count).or_exit("Cannot write triple", 1);
+
+        let table = counts.contingency(head, rel, dep, count);
+        if with_pmi {
+            write!(writer, "\t{:.4}", pmi(table)).or_exit("Cannot write triple", 1);
+        }
+        if with_llr {
+            write!(writer, "\t{:.4}", log_likelihood(table)).or_exit("Cannot write triple", 1);
+        }
+
+        writeln!(writer).or_exit("Cannot write triple", 1);
+    }
 }
@@ -10,7 +10,9 @@ use flate2::read::GzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
 
-pub fn create_writer<P>(filename: P, gzip: bool) -> io::Result<conllx::Writer<Box<dyn Write>>>
+use crate::comments::CommentedWriter;
+
+fn create_write<P>(filename: P, gzip: bool) -> io::Result<Box<dyn Write>>
 where
     P: AsRef<Path>,
 {
@@ -21,7 +23,14 @@ where
         Box::new(BufWriter::new(file))
     };
 
-    Ok(conllx::Writer::new(boxed_writer))
+    Ok(boxed_writer)
+}
+
+pub fn create_writer<P>(filename: P, gzip: bool) -> io::Result<conllx::Writer<Box<dyn Write>>>
+where
+    P: AsRef<Path>,
+{
+    Ok(conllx::Writer::new(create_write(filename, gzip)?))
 }
 
 pub fn open_writer<P>(path: &P) -> io::Result<conllx::Writer<Box<dyn Write>>>
@@ -32,6 +41,14 @@ where
     create_writer(path, compress)
 }
 
+pub fn open_commented_writer<P>(path: &P) -> io::Result<CommentedWriter<Box<dyn Write>>>
+where
+    P: AsRef<Path>,
+{
+    let compress = path.as_ref().extension() == Some(OsStr::new("gz"));
+    Ok(CommentedWriter::new(create_write(path, compress)?))
+}
+
 pub fn open_reader<P>(path: &P) -> io::Result<conllx::Reader<Box<dyn BufRead>>>
 where
     P: AsRef<Path>,
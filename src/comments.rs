@@ -0,0 +1,157 @@
+use std::io;
+use std::io::{BufRead, Cursor, Write};
+
+use conllx::{DisplaySentence, ReadSentence, Sentence};
+use failure::Error;
+
+/// A sentence with the comment lines that precede it.
+///
+/// The `conllx` reader does not accept comment lines. This data type
+/// stores comments such as `# sent_id = 1` separately from the sentence.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommentedSentence {
+    /// Comment lines, including the leading `#`.
+    pub comments: Vec<String>,
+
+    pub sentence: Sentence,
+}
+
+impl CommentedSentence {
+    pub fn new(comments: Vec<String>, sentence: Sentence) -> Self {
+        CommentedSentence { comments, sentence }
+    }
+
+    /// Get the value of a comment of the form `# key = value`.
+    pub fn comment_value(&self, key: &str) -> Option<&str> {
+        self.comments.iter().find_map(|comment| {
+            let mut parts = comment.trim_start_matches('#').splitn(2, '=');
+            let comment_key = parts.next()?.trim();
+            if comment_key == key {
+                Some(parts.next().map(str::trim).unwrap_or(""))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Check whether there is a comment of the form `# key` or
+    /// `# key = value`.
+    ///
+    /// The key also matches the first word of a comment, so that e.g.
+    /// `# newdoc id = doc1` is found with the key `newdoc`.
+    pub fn has_comment(&self, key: &str) -> bool {
+        self.comments.iter().any(|comment| {
+            let comment_key = comment
+                .trim_start_matches('#')
+                .split('=')
+                .next()
+                .unwrap_or("")
+                .trim();
+            comment_key == key || comment_key.split_whitespace().next() == Some(key)
+        })
+    }
+
+    /// Get the sentence identifier from the `sent_id` comment.
+    pub fn sent_id(&self) -> Option<&str> {
+        self.comment_value("sent_id")
+    }
+
+    /// Set the value of a comment of the form `# key = value`.
+    ///
+    /// An existing comment with the same key is replaced.
+    pub fn set_comment_value(&mut self, key: &str, value: &str) {
+        let line = format!("# {} = {}", key, value);
+
+        let idx = self.comments.iter().position(|comment| {
+            comment
+                .trim_start_matches('#')
+                .split('=')
+                .next()
+                .map(str::trim)
+                == Some(key)
+        });
+
+        match idx {
+            Some(idx) => self.comments[idx] = line,
+            None => self.comments.push(line),
+        }
+    }
+}
+
+/// A reader for CoNLL-X sentences that are preceded by comments.
+pub struct CommentedReader<R> {
+    read: R,
+}
+
+impl<R: BufRead> CommentedReader<R> {
+    pub fn new(read: R) -> Self {
+        CommentedReader { read }
+    }
+
+    pub fn read_sentence(&mut self) -> Result<Option<CommentedSentence>, Error> {
+        let mut line = String::new();
+        let mut comments = Vec::new();
+        let mut tokens = String::new();
+
+        loop {
+            line.clear();
+
+            if self.read.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                // Comments without a sentence are kept for the next sentence.
+                if tokens.is_empty() {
+                    if line.is_empty() {
+                        return Ok(None);
+                    }
+
+                    continue;
+                }
+
+                let sentence = conllx::Reader::new(Cursor::new(tokens))
+                    .read_sentence()?
+                    .expect("Sentence without tokens");
+
+                return Ok(Some(CommentedSentence { comments, sentence }));
+            }
+
+            if tokens.is_empty() && line.starts_with('#') {
+                comments.push(line.trim_end().to_owned());
+            } else {
+                tokens.push_str(&line);
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for CommentedReader<R> {
+    type Item = Result<CommentedSentence, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_sentence().transpose()
+    }
+}
+
+/// A writer for CoNLL-X sentences that are preceded by comments.
+pub struct CommentedWriter<W> {
+    write: W,
+    first: bool,
+}
+
+impl<W: Write> CommentedWriter<W> {
+    pub fn new(write: W) -> Self {
+        CommentedWriter { write, first: true }
+    }
+
+    pub fn write_sentence(&mut self, sentence: &CommentedSentence) -> io::Result<()> {
+        if self.first {
+            self.first = false;
+        } else {
+            write!(self.write, "\n\n")?;
+        }
+
+        for comment in &sentence.comments {
+            writeln!(self.write, "{}", comment)?;
+        }
+
+        write!(self.write, "{}", DisplaySentence(&sentence.sentence))
+    }
+}
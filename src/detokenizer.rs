@@ -0,0 +1,251 @@
+use std::str::FromStr;
+
+use conllx::{Features, Token};
+use failure::{format_err, Error};
+
+/// Languages with detokenization rules.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Language {
+    English,
+    French,
+    German,
+}
+
+impl FromStr for Language {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "en" => Ok(Language::English),
+            "fr" => Ok(Language::French),
+            "de" => Ok(Language::German),
+            _ => Err(format_err!("Unknown language: {}", s)),
+        }
+    }
+}
+
+impl Language {
+    fn opening_quotes(self) -> &'static [&'static str] {
+        match self {
+            Language::English => &["“", "‘"],
+            Language::French => &["«", "“"],
+            Language::German => &["„", "‚", "»"],
+        }
+    }
+
+    fn closing_quotes(self) -> &'static [&'static str] {
+        match self {
+            Language::English => &["”", "’"],
+            Language::French => &["»", "”"],
+            Language::German => &["“", "‘", "«"],
+        }
+    }
+}
+
+/// Tokens that are not preceded by a space.
+static NO_SPACE_BEFORE: &[&str] = &[
+    ",", ".", ";", ":", "!", "?", ")", "]", "}", "%", "...", "…", "n't", "'s", "'re", "'ve", "'ll",
+    "'d", "'m", "n’t", "’s", "’re", "’ve", "’ll", "’d", "’m",
+];
+
+/// Tokens that are not followed by a space.
+static NO_SPACE_AFTER: &[&str] = &["(", "[", "{"];
+
+/// Tokens that are preceded by a space in French.
+static FRENCH_SPACE_BEFORE: &[&str] = &[";", ":", "!", "?"];
+
+/// Rule-based detokenizer.
+///
+/// The spacing of a sentence is taken from the `SpaceAfter=No` annotations
+/// when any token of the sentence has one. `SpaceAfter` is read from the
+/// features and from the last column, which is the MISC column in CoNLL-U
+/// files. Otherwise, language-specific punctuation rules are used.
+pub struct Detokenizer {
+    language: Language,
+}
+
+impl Detokenizer {
+    pub fn new(language: Language) -> Self {
+        Detokenizer { language }
+    }
+
+    /// Reconstruct the text of a sentence from its forms.
+    pub fn detokenize(&self, tokens: &[Token]) -> String {
+        let mut text = String::new();
+
+        for (token, space) in tokens.iter().zip(self.spaces(tokens)) {
+            text.push_str(token.form());
+            if space {
+                text.push(' ');
+            }
+        }
+
+        text
+    }
+
+    /// Determine for every token whether it is followed by a space.
+    ///
+    /// The last token is never followed by a space.
+    pub fn spaces(&self, tokens: &[Token]) -> Vec<bool> {
+        let mut spaces: Vec<bool> = if tokens.iter().any(space_after_no) {
+            tokens.iter().map(|token| !space_after_no(token)).collect()
+        } else {
+            self.rule_spaces(tokens)
+        };
+
+        if let Some(last) = spaces.last_mut() {
+            *last = false;
+        }
+
+        spaces
+    }
+
+    fn rule_spaces(&self, tokens: &[Token]) -> Vec<bool> {
+        let mut spaces = vec![true; tokens.len()];
+
+        // Straight quotes alternate between opening and closing quotes.
+        let mut double_open = false;
+        let mut single_open = false;
+
+        for (idx, token) in tokens.iter().enumerate() {
+            let form = token.form();
+
+            let (opening, closing) = match form {
+                "\"" => {
+                    double_open = !double_open;
+                    (double_open, !double_open)
+                }
+                "'" => {
+                    single_open = !single_open;
+                    (single_open, !single_open)
+                }
+                _ => (
+                    self.language.opening_quotes().contains(&form),
+                    self.language.closing_quotes().contains(&form),
+                ),
+            };
+
+            let french = self.language == Language::French;
+            let french_quote = french && (form == "«" || form == "»");
+
+            if (opening && !french_quote) || NO_SPACE_AFTER.contains(&form) {
+                spaces[idx] = false;
+            }
+
+            if idx > 0 {
+                let space_before = (french && FRENCH_SPACE_BEFORE.contains(&form))
+                    || french_quote
+                    || !(closing || NO_SPACE_BEFORE.contains(&form));
+                if !space_before {
+                    spaces[idx - 1] = false;
+                }
+            }
+        }
+
+        spaces
+    }
+}
+
+/// Check whether a token is annotated with `SpaceAfter=No`.
+fn space_after_no(token: &Token) -> bool {
+    let features = token.features().map(Features::as_str);
+    let misc = token.p_head_rel();
+
+    features.into_iter().chain(misc).any(|annotations| {
+        annotations
+            .split('|')
+            .any(|annotation| annotation == "SpaceAfter=No" || annotation == "SpaceAfter:No")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use conllx::{Features, Token, TokenBuilder};
+
+    use super::{Detokenizer, Language};
+
+    fn detokenize(language: Language, forms: &[&str]) -> String {
+        let tokens: Vec<_> = forms.iter().map(|&form| Token::new(form)).collect();
+        Detokenizer::new(language).detokenize(&tokens)
+    }
+
+    #[test]
+    fn space_after_takes_precedence_over_rules() {
+        let tokens = vec![
+            Token::new("("),
+            TokenBuilder::new("a")
+                .features(Features::from_string("SpaceAfter=No"))
+                .token(),
+            Token::new(")"),
+            Token::new(","),
+        ];
+        assert_eq!(
+            Detokenizer::new(Language::English).detokenize(&tokens),
+            "( a) ,"
+        );
+
+        // SpaceAfter in the MISC column.
+        let tokens = vec![
+            TokenBuilder::new("Hello")
+                .p_head_rel("SpaceAfter=No")
+                .token(),
+            Token::new("world"),
+        ];
+        assert_eq!(
+            Detokenizer::new(Language::English).detokenize(&tokens),
+            "Helloworld"
+        );
+    }
+
+    #[test]
+    fn straight_quotes_alternate() {
+        assert_eq!(
+            detokenize(
+                Language::English,
+                &["He", "said", "\"", "hi", "\"", "and", "\"", "bye", "\"", "."]
+            ),
+            "He said \"hi\" and \"bye\"."
+        );
+        assert_eq!(
+            detokenize(Language::English, &["'", "Yes", "'", ",", "I", "do", "n't"]),
+            "'Yes', I don't"
+        );
+    }
+
+    #[test]
+    fn french_spacing() {
+        assert_eq!(
+            detokenize(
+                Language::French,
+                &["Quoi", "?", "Il", "dit", "«", "oui", "»", ";", "non", "!"]
+            ),
+            "Quoi ? Il dit « oui » ; non !"
+        );
+        assert_eq!(
+            detokenize(Language::French, &["Attention", ":", "fin", "."]),
+            "Attention : fin."
+        );
+    }
+
+    #[test]
+    fn german_quotes() {
+        assert_eq!(
+            detokenize(
+                Language::German,
+                &["Er", "sagte", "„", "ja", "“", "und", "ging", "."]
+            ),
+            "Er sagte „ja“ und ging."
+        );
+    }
+
+    #[test]
+    fn brackets() {
+        assert_eq!(
+            detokenize(
+                Language::English,
+                &["A", "(", "small", ")", "test", "[", "1", "]", "."]
+            ),
+            "A (small) test [1]."
+        );
+    }
+}
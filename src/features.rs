@@ -0,0 +1,61 @@
+use std::cmp::Ordering;
+
+/// Ordered feature-value pairs.
+///
+/// In contrast to `conllx::Features`, the order of the features is
+/// preserved and the separator between features and values can be
+/// chosen.
+pub type FeatureList = Vec<(String, Option<String>)>;
+
+/// Parse a feature string.
+///
+/// Features are separated by a vertical bar (`|`), a feature and its
+/// value by `separator`.
+pub fn parse_features(features: &str, separator: char) -> FeatureList {
+    features
+        .split('|')
+        .filter(|fv| !fv.is_empty())
+        .map(|fv| {
+            let mut iter = fv.splitn(2, separator);
+            let feature = iter.next().unwrap().to_owned();
+            (feature, iter.next().map(ToOwned::to_owned))
+        })
+        .collect()
+}
+
+/// Convert a feature list to a string.
+///
+/// Returns `None` if the feature list is empty.
+pub fn features_to_string(
+    features: &[(String, Option<String>)],
+    separator: char,
+) -> Option<String> {
+    if features.is_empty() {
+        return None;
+    }
+
+    Some(
+        features
+            .iter()
+            .map(|(feature, value)| match value {
+                Some(value) => format!("{}{}{}", feature, separator, value),
+                None => feature.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join("|"),
+    )
+}
+
+/// Sort features in canonical order.
+///
+/// Features are sorted case-insensitively by feature name, as in
+/// Universal Dependencies.
+pub fn sort_features(features: &mut FeatureList) {
+    features.sort_by(|(f1, _), (f2, _)| compare_case_insensitive(f1, f2));
+}
+
+fn compare_case_insensitive(s1: &str, s2: &str) -> Ordering {
+    s1.to_lowercase()
+        .cmp(&s2.to_lowercase())
+        .then_with(|| s1.cmp(s2))
+}
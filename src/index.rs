@@ -0,0 +1,327 @@
+use std::fs::File;
+use std::io;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use failure::{ensure, Error};
+
+use crate::comments::{CommentedReader, CommentedSentence};
+
+static MAGIC: &[u8] = b"CNLXIDX2";
+
+/// Byte offsets of the sentences in a corpus.
+///
+/// The index is used to read arbitrary sentences from a corpus without
+/// reading the corpus into memory.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SentenceIndex {
+    corpus_len: u64,
+    corpus_mtime: u64,
+    offsets: Vec<u64>,
+}
+
+impl SentenceIndex {
+    /// Build an index by scanning a corpus.
+    ///
+    /// Since the modification time of the corpus is unknown, the index
+    /// is never considered to be up to date by `open`.
+    pub fn build(mut read: impl BufRead) -> io::Result<Self> {
+        let mut offsets = Vec::new();
+        let mut offset = 0;
+        let mut line = Vec::new();
+
+        // Offset of the first line of the current sentence. Comments
+        // without a sentence belong to the next sentence.
+        let mut start = None;
+        let mut in_sentence = false;
+
+        loop {
+            line.clear();
+            let n = read.read_until(b'\n', &mut line)?;
+            if n == 0 {
+                break;
+            }
+
+            if line.iter().all(u8::is_ascii_whitespace) {
+                if in_sentence {
+                    start = None;
+                    in_sentence = false;
+                }
+            } else {
+                let start = *start.get_or_insert(offset);
+                if !in_sentence && line[0] != b'#' {
+                    offsets.push(start);
+                    in_sentence = true;
+                }
+            }
+
+            offset += n as u64;
+        }
+
+        Ok(SentenceIndex {
+            corpus_len: offset,
+            corpus_mtime: 0,
+            offsets,
+        })
+    }
+
+    /// Build an index for the corpus stored in a file.
+    pub fn build_for_file(corpus: impl AsRef<Path>) -> io::Result<Self> {
+        let corpus_mtime = modification_time(&corpus)?;
+        let mut index = Self::build(BufReader::new(File::open(corpus)?))?;
+        index.corpus_mtime = corpus_mtime;
+        Ok(index)
+    }
+
+    /// Get the path of the index file of a corpus.
+    ///
+    /// The index is stored alongside the corpus, with the `.idx` suffix.
+    pub fn index_path(corpus: impl AsRef<Path>) -> PathBuf {
+        let mut path = corpus.as_ref().as_os_str().to_owned();
+        path.push(".idx");
+        path.into()
+    }
+
+    /// Open the index of a corpus.
+    ///
+    /// The index is read from the index file if it is present and up to
+    /// date, i.e. the length and the modification time of the corpus did
+    /// not change since the index was built. Otherwise, a warning is
+    /// printed to stderr and the index is built by scanning the corpus.
+    pub fn open(corpus: impl AsRef<Path>) -> Result<Self, Error> {
+        let corpus_len = corpus.as_ref().metadata()?.len();
+        let corpus_mtime = modification_time(&corpus)?;
+        let index_path = Self::index_path(&corpus);
+
+        if index_path.exists() {
+            // Indexes in an older format or invalid indexes are rebuilt as well.
+            if let Ok(index) = Self::read(BufReader::new(File::open(&index_path)?)) {
+                if index.corpus_mtime != 0
+                    && index.corpus_len == corpus_len
+                    && index.corpus_mtime == corpus_mtime
+                {
+                    return Ok(index);
+                }
+            }
+
+            eprintln!(
+                "Warning: index {} is out of date or invalid, scanning the corpus \
+                 (run conllx-index to update it)",
+                index_path.to_string_lossy()
+            );
+        } else {
+            eprintln!(
+                "Warning: no index {}, scanning the corpus (run conllx-index to create it)",
+                index_path.to_string_lossy()
+            );
+        }
+
+        Ok(Self::build_for_file(corpus)?)
+    }
+
+    /// Read an index.
+    ///
+    /// Fails if the index is truncated or its offsets are not strictly
+    /// increasing and within the corpus.
+    pub fn read(mut read: impl Read) -> Result<Self, Error> {
+        let mut magic = [0u8; 8];
+        read.read_exact(&mut magic)?;
+        ensure!(magic == MAGIC, "Not a sentence index");
+
+        let corpus_len = read_u64(&mut read)?;
+        let corpus_mtime = read_u64(&mut read)?;
+        let n_sentences = read_u64(&mut read)?;
+
+        // The number of sentences is not used to preallocate, since an
+        // invalid index could claim an arbitrary number of sentences.
+        let mut offsets: Vec<u64> = Vec::new();
+        for _ in 0..n_sentences {
+            let offset = read_u64(&mut read)?;
+            ensure!(offset < corpus_len, "Sentence offset beyond the corpus");
+            ensure!(
+                offsets.last().map(|&last| offset > last).unwrap_or(true),
+                "Sentence offsets are not increasing"
+            );
+            offsets.push(offset);
+        }
+
+        Ok(SentenceIndex {
+            corpus_len,
+            corpus_mtime,
+            offsets,
+        })
+    }
+
+    /// Write the index.
+    ///
+    /// The index starts with a magic number, followed by the corpus length
+    /// in bytes, the modification time of the corpus in nanoseconds since
+    /// the Unix epoch, the number of sentences, and the offsets of the
+    /// sentences. All numbers are stored as little-endian 64-bit integers.
+    pub fn write(&self, mut write: impl Write) -> io::Result<()> {
+        write.write_all(MAGIC)?;
+        write.write_all(&self.corpus_len.to_le_bytes())?;
+        write.write_all(&self.corpus_mtime.to_le_bytes())?;
+        write.write_all(&(self.offsets.len() as u64).to_le_bytes())?;
+
+        for offset in &self.offsets {
+            write.write_all(&offset.to_le_bytes())?;
+        }
+
+        write.flush()
+    }
+
+    /// Write the index to the index file of a corpus.
+    pub fn write_for_file(&self, corpus: impl AsRef<Path>) -> io::Result<()> {
+        self.write(BufWriter::new(File::create(Self::index_path(corpus))?))
+    }
+
+    /// Is the index empty?
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// The number of sentences in the corpus.
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Get the byte offset of a sentence.
+    pub fn offset(&self, idx: usize) -> Option<u64> {
+        self.offsets.get(idx).cloned()
+    }
+}
+
+/// Get the modification time of a file in nanoseconds since the Unix epoch.
+fn modification_time(path: impl AsRef<Path>) -> io::Result<u64> {
+    let mtime = path.as_ref().metadata()?.modified()?;
+    Ok(mtime
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_nanos() as u64)
+        .unwrap_or(0))
+}
+
+fn read_u64(read: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    read.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+/// A reader that provides random access to the sentences of a corpus.
+pub struct IndexedReader<R> {
+    read: R,
+    index: SentenceIndex,
+}
+
+impl<R: BufRead + Seek> IndexedReader<R> {
+    pub fn new(read: R, index: SentenceIndex) -> Self {
+        IndexedReader { read, index }
+    }
+
+    /// Get the index of the corpus.
+    pub fn index(&self) -> &SentenceIndex {
+        &self.index
+    }
+
+    /// Read the sentence with the given (0-based) index.
+    ///
+    /// Returns `None` when the corpus has no sentence with that index.
+    pub fn sentence(&mut self, idx: usize) -> Result<Option<CommentedSentence>, Error> {
+        let offset = match self.index.offset(idx) {
+            Some(offset) => offset,
+            None => return Ok(None),
+        };
+
+        self.read.seek(SeekFrom::Start(offset))?;
+
+        CommentedReader::new(&mut self.read).read_sentence()
+    }
+}
+
+impl IndexedReader<BufReader<File>> {
+    /// Open a corpus for random access, using its index file when available.
+    pub fn open(corpus: impl AsRef<Path>) -> Result<Self, Error> {
+        let index = SentenceIndex::open(&corpus)?;
+        Ok(IndexedReader::new(
+            BufReader::new(File::open(corpus)?),
+            index,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::{IndexedReader, SentenceIndex};
+
+    static CORPUS: &str = "# newdoc id = d1
+# sent_id = s1
+1\tDas\t_\t_\t_\t_\t2\tdet\t_\t_
+2\tHaus\t_\t_\t_\t_\t0\tROOT\t_\t_
+
+
+# sent_id = s2
+1\tJa\t_\t_\t_\t_\t0\tROOT\t_\t_
+
+# sent_id = s3
+1\tNein\t_\t_\t_\t_\t0\tROOT\t_\t_
+";
+
+    fn round_trip(index: &SentenceIndex) -> SentenceIndex {
+        let mut data = Vec::new();
+        index.write(&mut data).unwrap();
+        SentenceIndex::read(Cursor::new(data)).unwrap()
+    }
+
+    #[test]
+    fn build_write_read_round_trip() {
+        let index = SentenceIndex::build(Cursor::new(CORPUS)).unwrap();
+        assert_eq!(index.len(), 3);
+        assert_eq!(round_trip(&index), index);
+    }
+
+    #[test]
+    fn indexed_reader_reads_sentences() {
+        let index = round_trip(&SentenceIndex::build(Cursor::new(CORPUS)).unwrap());
+        let mut reader = IndexedReader::new(Cursor::new(CORPUS), index);
+
+        for &idx in &[2, 0, 1] {
+            let sentence = reader.sentence(idx).unwrap().unwrap();
+            assert_eq!(sentence.sent_id(), Some(format!("s{}", idx + 1).as_str()));
+        }
+
+        let first = reader.sentence(0).unwrap().unwrap();
+        assert_eq!(first.comments, vec!["# newdoc id = d1", "# sent_id = s1"]);
+        assert_eq!(first.sentence.len(), 2);
+
+        assert!(reader.sentence(3).unwrap().is_none());
+    }
+
+    fn header(corpus_len: u64, n_sentences: u64) -> Vec<u8> {
+        let mut data = b"CNLXIDX2".to_vec();
+        data.extend_from_slice(&corpus_len.to_le_bytes());
+        data.extend_from_slice(&1u64.to_le_bytes());
+        data.extend_from_slice(&n_sentences.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn rejects_invalid_sentence_count() {
+        let mut data = header(100, 1 << 62);
+        data.extend_from_slice(&0u64.to_le_bytes());
+        assert!(SentenceIndex::read(Cursor::new(data)).is_err());
+    }
+
+    #[test]
+    fn rejects_invalid_offsets() {
+        for offsets in &[[0u64, 100], [10, 10], [10, 5]] {
+            let mut data = header(100, 2);
+            for offset in offsets {
+                data.extend_from_slice(&offset.to_le_bytes());
+            }
+            assert!(SentenceIndex::read(Cursor::new(data)).is_err());
+        }
+    }
+}
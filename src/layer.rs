@@ -1,6 +1,7 @@
 use std::borrow::Cow;
 
 use conllx::{Features, Token};
+use failure::{format_err, Error};
 
 pub type LayerCallback = Box<dyn Fn(&Token) -> Option<Cow<'_, str>>>;
 
@@ -24,3 +25,123 @@ pub fn layer_callback(layer: &str) -> Option<LayerCallback> {
         _ => None,
     }
 }
+
+/// Get a callback for a feature.
+pub fn feature_callback(feature: impl Into<String>) -> LayerCallback {
+    let feature = feature.into();
+
+    Box::new(move |token| match token.features() {
+        Some(features) => features
+            .as_map()
+            .get(&feature)
+            .map(Option::as_ref)
+            .unwrap_or(None)
+            .map(|s| Cow::Borrowed(s.as_str())),
+        None => None,
+    })
+}
+
+/// Get a callback for a layer or a feature.
+///
+/// Layers are specified as in `layer_callback`, features as
+/// `feature:NAME`.
+pub fn layer_or_feature_callback(spec: &str) -> Option<LayerCallback> {
+    match spec.strip_prefix("feature:") {
+        Some(feature) => Some(feature_callback(feature)),
+        None => layer_callback(spec),
+    }
+}
+
+/// A condition on a layer or feature of a token.
+pub struct Condition {
+    callback: LayerCallback,
+    value: String,
+}
+
+impl Condition {
+    /// Parse a condition of the form `LAYER=VALUE`.
+    ///
+    /// The layer is specified as in `layer_or_feature_callback`.
+    pub fn parse(condition: &str) -> Result<Self, Error> {
+        let mut parts = condition.splitn(2, '=');
+        let layer = parts.next().unwrap();
+        let value = parts.next().ok_or_else(|| {
+            format_err!("Condition should be of the form LAYER=VALUE: {}", condition)
+        })?;
+
+        let callback = layer_or_feature_callback(layer)
+            .ok_or_else(|| format_err!("Unknown layer: {}", layer))?;
+
+        Ok(Condition {
+            callback,
+            value: value.to_owned(),
+        })
+    }
+
+    /// Check whether the condition holds for a token.
+    ///
+    /// An absent layer or feature matches the value `_`.
+    pub fn matches(&self, token: &Token) -> bool {
+        match (self.callback)(token) {
+            Some(value) => value == self.value,
+            None => self.value == "_",
+        }
+    }
+}
+
+pub type LayerSetter = Box<dyn Fn(&mut Token, Option<&str>) -> Result<(), Error>>;
+
+/// Get a setter for a layer.
+///
+/// The layer names are the same as those of `layer_callback`. Setting
+/// a layer to `None` clears the layer. Since the form cannot be absent,
+/// clearing the form sets it to `_`.
+pub fn layer_setter(layer: &str) -> Option<LayerSetter> {
+    match layer {
+        "cpos" => Some(Box::new(|t, v| {
+            t.set_cpos(v);
+            Ok(())
+        })),
+        "features" => Some(Box::new(|t, v| {
+            t.set_features(v.map(Features::from_string));
+            Ok(())
+        })),
+        "form" => Some(Box::new(|t, v| {
+            t.set_form(v.unwrap_or("_"));
+            Ok(())
+        })),
+        "head" => Some(Box::new(|t, v| {
+            t.set_head(parse_head(v)?);
+            Ok(())
+        })),
+        "headrel" => Some(Box::new(|t, v| {
+            t.set_head_rel(v);
+            Ok(())
+        })),
+        "phead" => Some(Box::new(|t, v| {
+            t.set_p_head(parse_head(v)?);
+            Ok(())
+        })),
+        "pheadrel" => Some(Box::new(|t, v| {
+            t.set_p_head_rel(v);
+            Ok(())
+        })),
+        "lemma" => Some(Box::new(|t, v| {
+            t.set_lemma(v);
+            Ok(())
+        })),
+        "pos" => Some(Box::new(|t, v| {
+            t.set_pos(v);
+            Ok(())
+        })),
+        _ => None,
+    }
+}
+
+fn parse_head(head: Option<&str>) -> Result<Option<usize>, Error> {
+    head.map(|h| {
+        h.parse()
+            .map_err(|_| format_err!("Cannot parse head: {}", h))
+    })
+    .transpose()
+}
@@ -1,15 +1,45 @@
 mod cmd;
-pub use crate::cmd::{open_reader, open_writer, or_exit};
+pub use crate::cmd::{open_commented_writer, open_reader, open_writer, or_exit};
+
+mod comments;
+pub use crate::comments::{CommentedReader, CommentedSentence, CommentedWriter};
+
+mod detokenizer;
+pub use crate::detokenizer::{Detokenizer, Language};
+
+mod features;
+pub use crate::features::{features_to_string, parse_features, sort_features, FeatureList};
 
 mod graph;
 pub use crate::graph::{first_matching_edge, sentence_to_graph, DependencyGraph, DependencyNode};
 
+mod index;
+pub use crate::index::{IndexedReader, SentenceIndex};
+
 pub mod io;
 
 mod layer;
-pub use crate::layer::{layer_callback, LayerCallback};
+pub use crate::layer::{
+    feature_callback, layer_callback, layer_or_feature_callback, layer_setter, Condition,
+    LayerCallback, LayerSetter,
+};
 
 pub mod layer_ng;
 
+mod rng;
+pub use crate::rng::SeededRng;
+
+mod shuffle;
+pub use crate::shuffle::external_shuffle;
+
+mod tokenizer;
+pub use crate::tokenizer::Tokenizer;
+
 mod unicode;
-pub use crate::unicode::{simplify_unicode, simplify_unicode_punct, Normalization};
+pub use crate::unicode::{
+    fullwidth_to_ascii, is_invisible, normalize, normalize_whitespace, remove_diacritics,
+    simplify_unicode, simplify_unicode_punct, Conversion, Normalization,
+};
+
+mod units;
+pub use crate::units::{Unit, Units};
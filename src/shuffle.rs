@@ -0,0 +1,162 @@
+use std::env;
+use std::fs;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter, Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use failure::Error;
+
+use crate::comments::{CommentedReader, CommentedSentence, CommentedWriter};
+use crate::rng::SeededRng;
+
+/// Shuffle units of sentences using temporary files.
+///
+/// Units (see `Units`) are scattered randomly over `n_buckets` temporary
+/// bucket files. Then every bucket is read into memory, shuffled, and
+/// written. Since every unit is assigned to a bucket uniformly at random
+/// and buckets are shuffled uniformly, the result is a uniformly random
+/// permutation. Only one bucket is kept in memory at a time.
+///
+/// The bucket files are stored in a directory in `std::env::temp_dir()`,
+/// which is removed afterwards.
+pub fn external_shuffle<I, W>(
+    rng: &mut SeededRng,
+    units: I,
+    n_buckets: usize,
+    writer: &mut CommentedWriter<W>,
+) -> Result<(), Error>
+where
+    I: IntoIterator<Item = Result<Vec<CommentedSentence>, Error>>,
+    W: Write,
+{
+    assert!(n_buckets > 0, "At least one bucket is required");
+
+    let temp_dir = TempDir::new("conllx-shuffle")?;
+
+    let mut buckets = (0..n_buckets)
+        .map(|bucket| File::create(temp_dir.bucket_path(bucket)).map(BucketWriter::new))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for unit in units {
+        let unit = unit?;
+        let bucket = rng.index(n_buckets);
+        buckets[bucket].write_record(&unit)?;
+    }
+
+    for bucket in &mut buckets {
+        bucket.flush()?;
+    }
+    drop(buckets);
+
+    for bucket in 0..n_buckets {
+        let mut units = read_bucket(&temp_dir.bucket_path(bucket))?;
+        rng.shuffle(&mut units);
+
+        for sentence in units.iter().flatten() {
+            writer.write_sentence(sentence)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writer for bucket files.
+///
+/// Every record is stored as its length in bytes (a little-endian 64-bit
+/// integer), followed by the sentences of a unit in CoNLL-X format.
+struct BucketWriter {
+    write: BufWriter<File>,
+    buf: Vec<u8>,
+}
+
+impl BucketWriter {
+    fn new(file: File) -> Self {
+        BucketWriter {
+            write: BufWriter::new(file),
+            buf: Vec::new(),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write.flush()
+    }
+
+    fn write_record(&mut self, unit: &[CommentedSentence]) -> io::Result<()> {
+        self.buf.clear();
+
+        let mut writer = CommentedWriter::new(&mut self.buf);
+        for sentence in unit {
+            writer.write_sentence(sentence)?;
+        }
+
+        self.write
+            .write_all(&(self.buf.len() as u64).to_le_bytes())?;
+        self.write.write_all(&self.buf)
+    }
+}
+
+fn read_bucket(path: &Path) -> Result<Vec<Vec<CommentedSentence>>, Error> {
+    let mut read = BufReader::new(File::open(path)?);
+    let mut units = Vec::new();
+
+    loop {
+        let mut len = [0u8; 8];
+        match read.read_exact(&mut len) {
+            Ok(()) => (),
+            Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        }
+
+        let mut buf = vec![0u8; u64::from_le_bytes(len) as usize];
+        read.read_exact(&mut buf)?;
+
+        let unit = CommentedReader::new(Cursor::new(buf)).collect::<Result<_, _>>()?;
+        units.push(unit);
+    }
+
+    Ok(units)
+}
+
+/// Temporary directory that is removed when dropped.
+struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    fn new(prefix: &str) -> io::Result<Self> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.subsec_nanos())
+            .unwrap_or(0);
+
+        let mut attempt = 0;
+        loop {
+            let path = env::temp_dir().join(format!(
+                "{}-{}-{}-{}",
+                prefix,
+                process::id(),
+                nanos,
+                attempt
+            ));
+
+            match fs::create_dir(&path) {
+                Ok(()) => return Ok(TempDir { path }),
+                Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists => attempt += 1,
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    fn bucket_path(&self, bucket: usize) -> PathBuf {
+        self.path.join(format!("bucket-{}", bucket))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
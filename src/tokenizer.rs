@@ -0,0 +1,290 @@
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// Abbreviations that are known to the tokenizer by default.
+static ABBREVIATIONS: &[&str] = &[
+    // English
+    "apr.", "aug.", "ca.", "cf.", "co.", "corp.", "dec.", "dept.", "dr.", "e.g.", "etc.", "feb.",
+    "fig.", "i.e.", "inc.", "jan.", "jr.", "jul.", "jun.", "ltd.", "mar.", "mr.", "mrs.", "ms.",
+    "no.", "nov.", "oct.", "p.", "pp.", "prof.", "sep.", "sept.", "sr.", "st.", "vol.", "vs.",
+    // German
+    "abb.", "abs.", "bzw.", "d.h.", "evtl.", "fr.", "ggf.", "hr.", "inkl.", "nr.", "s.", "sog.",
+    "str.", "u.a.", "usw.", "vgl.", "z.b.", "zzgl.",
+];
+
+/// Clitics that are split off from the preceding word.
+static CLITICS: &[&str] = &[
+    "n't", "'s", "'re", "'ve", "'ll", "'d", "'m", "n’t", "’s", "’re", "’ve", "’ll", "’d", "’m",
+];
+
+/// Characters that end a sentence.
+static TERMINALS: &[char] = &['.', '!', '?', '…'];
+
+/// Characters that can follow the end of a sentence.
+static CLOSING: &[char] = &['"', '\'', ')', ']', '}', '»', '”', '’'];
+
+/// Rule-based tokenizer and sentence splitter.
+///
+/// Tokens are separated by whitespace. Punctuation at the beginning and
+/// end of a word is split off, where a run of the same punctuation
+/// character (such as `...`) forms a single token. Periods are kept
+/// attached to abbreviations, initials (`J.`), and abbreviations with
+/// internal periods (`U.S.`). English clitics such as `n't` and `'s` are
+/// split off from their host.
+///
+/// Tokens are returned as byte ranges in the text, so that the text of a
+/// sentence can be recovered.
+pub struct Tokenizer {
+    abbreviations: HashSet<String>,
+}
+
+impl Tokenizer {
+    /// Construct a tokenizer with the built-in abbreviations.
+    pub fn new() -> Self {
+        Tokenizer {
+            abbreviations: ABBREVIATIONS.iter().map(|&abbr| abbr.to_owned()).collect(),
+        }
+    }
+
+    /// Add an abbreviation, with or without the final period.
+    pub fn add_abbreviation(&mut self, abbreviation: &str) {
+        let mut abbreviation = abbreviation.trim().to_lowercase();
+        if !abbreviation.ends_with('.') {
+            abbreviation.push('.');
+        }
+
+        self.abbreviations.insert(abbreviation);
+    }
+
+    /// Tokenize a text.
+    pub fn tokenize(&self, text: &str) -> Vec<Range<usize>> {
+        let mut tokens = Vec::new();
+
+        let mut start = None;
+        for (idx, c) in text.char_indices() {
+            match (c.is_whitespace(), start) {
+                (true, Some(word_start)) => {
+                    self.tokenize_word(text, word_start, idx, &mut tokens);
+                    start = None;
+                }
+                (false, None) => start = Some(idx),
+                _ => (),
+            }
+        }
+
+        if let Some(word_start) = start {
+            self.tokenize_word(text, word_start, text.len(), &mut tokens);
+        }
+
+        tokens
+    }
+
+    /// Split a tokenized text into sentences.
+    ///
+    /// A sentence ends after a token consisting of `.`, `!`, `?`, or `…`
+    /// (and closing quotes or brackets) when the next token does not start
+    /// with a lowercase letter.
+    pub fn split_sentences(&self, text: &str, tokens: Vec<Range<usize>>) -> Vec<Vec<Range<usize>>> {
+        let mut sentences = Vec::new();
+        let mut sentence = Vec::new();
+        let mut ended = false;
+
+        for token in tokens {
+            let form = &text[token.clone()];
+
+            if ended {
+                if form.chars().all(|c| CLOSING.contains(&c)) {
+                    sentence.push(token);
+                    continue;
+                }
+
+                if !form.chars().next().map(char::is_lowercase).unwrap_or(false) {
+                    sentences.push(sentence);
+                    sentence = Vec::new();
+                }
+            }
+
+            ended = form.chars().all(|c| TERMINALS.contains(&c));
+            sentence.push(token);
+        }
+
+        if !sentence.is_empty() {
+            sentences.push(sentence);
+        }
+
+        sentences
+    }
+
+    fn tokenize_word(&self, text: &str, start: usize, end: usize, tokens: &mut Vec<Range<usize>>) {
+        // Leading punctuation.
+        let mut start = start;
+        while start < end {
+            let c = text[start..end].chars().next().unwrap();
+            if c.is_alphanumeric() {
+                break;
+            }
+
+            let len = run_len(text[start..end].chars(), c);
+            tokens.push(start..start + len);
+            start += len;
+        }
+
+        // Trailing punctuation.
+        let mut end = end;
+        let mut trailing = Vec::new();
+        while end > start {
+            let c = text[start..end].chars().next_back().unwrap();
+            if c.is_alphanumeric() || (c == '.' && self.is_abbreviation(&text[start..end])) {
+                break;
+            }
+
+            let len = run_len(text[start..end].chars().rev(), c);
+            trailing.push(end - len..end);
+            end -= len;
+        }
+
+        if start < end {
+            match clitic_len(&text[start..end]) {
+                Some(len) => {
+                    tokens.push(start..end - len);
+                    tokens.push(end - len..end);
+                }
+                None => tokens.push(start..end),
+            }
+        }
+
+        tokens.extend(trailing.into_iter().rev());
+    }
+
+    fn is_abbreviation(&self, word: &str) -> bool {
+        if self.abbreviations.contains(&word.to_lowercase()) {
+            return true;
+        }
+
+        // Initials and abbreviations with internal periods.
+        let stem = &word[..word.len() - 1];
+        !stem.is_empty()
+            && stem.split('.').all(|part| {
+                let mut chars = part.chars();
+                chars.next().map(char::is_alphabetic).unwrap_or(false) && chars.next().is_none()
+            })
+    }
+}
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Length in bytes of the run of `c` at the start of `chars`.
+fn run_len(chars: impl Iterator<Item = char>, c: char) -> usize {
+    chars.take_while(|&other| other == c).count() * c.len_utf8()
+}
+
+/// Length in bytes of the clitic at the end of a word.
+fn clitic_len(word: &str) -> Option<usize> {
+    CLITICS
+        .iter()
+        .find(|clitic| {
+            word.len() > clitic.len()
+                && word.is_char_boundary(word.len() - clitic.len())
+                && word[word.len() - clitic.len()..].eq_ignore_ascii_case(clitic)
+        })
+        .map(|clitic| clitic.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tokenizer;
+
+    fn tokens<'a>(tokenizer: &Tokenizer, text: &'a str) -> Vec<&'a str> {
+        tokenizer
+            .tokenize(text)
+            .into_iter()
+            .map(|token| &text[token])
+            .collect()
+    }
+
+    fn sentences(text: &str) -> Vec<Vec<&str>> {
+        let tokenizer = Tokenizer::new();
+        tokenizer
+            .split_sentences(text, tokenizer.tokenize(text))
+            .into_iter()
+            .map(|sentence| sentence.into_iter().map(|token| &text[token]).collect())
+            .collect()
+    }
+
+    #[test]
+    fn abbreviations_keep_their_period() {
+        let tokenizer = Tokenizer::new();
+        assert_eq!(
+            tokens(&tokenizer, "Dr. Smith sah z.B. Mr. Brown."),
+            vec!["Dr.", "Smith", "sah", "z.B.", "Mr.", "Brown", "."]
+        );
+        assert_eq!(tokens(&tokenizer, "approx. 5"), vec!["approx", ".", "5"]);
+
+        let mut tokenizer = Tokenizer::new();
+        tokenizer.add_abbreviation("Approx");
+        assert_eq!(tokens(&tokenizer, "approx. 5"), vec!["approx.", "5"]);
+    }
+
+    #[test]
+    fn initials_keep_their_periods() {
+        let tokenizer = Tokenizer::new();
+        assert_eq!(
+            tokens(&tokenizer, "J. R. R. Tolkien"),
+            vec!["J.", "R.", "R.", "Tolkien"]
+        );
+        assert_eq!(
+            tokens(&tokenizer, "the U.S. army (U.S.A.)"),
+            vec!["the", "U.S.", "army", "(", "U.S.A.", ")"]
+        );
+    }
+
+    #[test]
+    fn clitics_are_split_off() {
+        let tokenizer = Tokenizer::new();
+        assert_eq!(
+            tokens(&tokenizer, "I don't know John's dog, we'll see."),
+            vec!["I", "do", "n't", "know", "John", "'s", "dog", ",", "we", "'ll", "see", "."]
+        );
+        assert_eq!(tokens(&tokenizer, "DON’T"), vec!["DO", "N’T"]);
+    }
+
+    #[test]
+    fn punctuation_runs_form_one_token() {
+        let tokenizer = Tokenizer::new();
+        assert_eq!(
+            tokens(&tokenizer, "\"Wait...\" (yes!!!)"),
+            vec!["\"", "Wait", "...", "\"", "(", "yes", "!!!", ")"]
+        );
+        assert_eq!(tokens(&tokenizer, "?!"), vec!["?", "!"]);
+    }
+
+    #[test]
+    fn sentences_are_split_after_terminals() {
+        assert_eq!(
+            sentences("He left. She stayed!"),
+            vec![vec!["He", "left", "."], vec!["She", "stayed", "!"]]
+        );
+
+        // Closing quotes and brackets belong to the preceding sentence.
+        assert_eq!(
+            sentences("\"Go home.\" He went."),
+            vec![vec!["\"", "Go", "home", ".", "\""], vec!["He", "went", "."]]
+        );
+    }
+
+    #[test]
+    fn sentences_are_not_split_before_lowercase_or_after_abbreviations() {
+        assert_eq!(
+            sentences("Wait... what? Yes."),
+            vec![vec!["Wait", "...", "what", "?"], vec!["Yes", "."]]
+        );
+        assert_eq!(
+            sentences("Ask Dr. Smith."),
+            vec![vec!["Ask", "Dr.", "Smith", "."]]
+        );
+    }
+}
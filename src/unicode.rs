@@ -1,3 +1,4 @@
+use unicode_normalization::char::is_combining_mark;
 use unicode_normalization::UnicodeNormalization;
 
 /// Types of unicode normalization.
@@ -118,6 +119,11 @@ pub fn simplify_unicode_punct(c: char) -> Conversion {
     }
 }
 
+/// Apply a Unicode normalization to a string.
+pub fn normalize(s: &str, norm: Normalization) -> String {
+    normalization_iter(s.chars(), norm).collect()
+}
+
 pub fn simplify_unicode(s: &str, norm: Normalization) -> String {
     normalization_iter(s.chars(), norm).fold(String::with_capacity(s.len()), |mut s, c| {
         match simplify_unicode_punct(c) {
@@ -129,3 +135,32 @@ pub fn simplify_unicode(s: &str, norm: Normalization) -> String {
         s
     })
 }
+
+/// Check whether a character is a control or zero-width character.
+pub fn is_invisible(c: char) -> bool {
+    match c {
+        '\u{00ad}' | '\u{200b}'..='\u{200f}' | '\u{2060}'..='\u{2064}' | '\u{feff}' => true,
+        _ => c.is_control(),
+    }
+}
+
+/// Map fullwidth ASCII characters and the ideographic space to ASCII.
+pub fn fullwidth_to_ascii(c: char) -> char {
+    match c {
+        '\u{ff01}'..='\u{ff5e}' => std::char::from_u32(c as u32 - 0xff01 + 0x21).unwrap(),
+        '\u{3000}' => ' ',
+        _ => c,
+    }
+}
+
+/// Remove diacritics by removing combining marks after decomposition.
+///
+/// The result is in NFC.
+pub fn remove_diacritics(s: &str) -> String {
+    s.nfd().filter(|&c| !is_combining_mark(c)).nfc().collect()
+}
+
+/// Replace runs of whitespace by a single space and trim whitespace.
+pub fn normalize_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
@@ -0,0 +1,100 @@
+use std::str::FromStr;
+
+use failure::{format_err, Error};
+
+use crate::comments::CommentedSentence;
+
+/// Units in which sentences are shuffled, partitioned, or split.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Unit {
+    /// Every sentence is a unit.
+    Sentence,
+
+    /// A paragraph starts at a `# newpar` or `# newdoc` comment.
+    Paragraph,
+
+    /// A document starts at a `# newdoc` comment.
+    Document,
+}
+
+impl Unit {
+    /// Check whether a sentence starts a new unit.
+    fn starts_unit(self, sentence: &CommentedSentence) -> bool {
+        match self {
+            Unit::Sentence => true,
+            Unit::Paragraph => sentence.has_comment("newpar") || sentence.has_comment("newdoc"),
+            Unit::Document => sentence.has_comment("newdoc"),
+        }
+    }
+}
+
+impl FromStr for Unit {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sentence" => Ok(Unit::Sentence),
+            "paragraph" => Ok(Unit::Paragraph),
+            "document" => Ok(Unit::Document),
+            _ => Err(format_err!("Unknown unit: {}", s)),
+        }
+    }
+}
+
+/// Iterator that groups sentences into units.
+///
+/// Sentences before the first unit boundary form a unit as well. The
+/// order of sentences within a unit is preserved.
+pub struct Units<I> {
+    sentences: I,
+    unit: Unit,
+    next: Option<CommentedSentence>,
+}
+
+impl<I> Units<I>
+where
+    I: Iterator<Item = Result<CommentedSentence, Error>>,
+{
+    pub fn new(sentences: I, unit: Unit) -> Self {
+        Units {
+            sentences,
+            unit,
+            next: None,
+        }
+    }
+}
+
+impl<I> Iterator for Units<I>
+where
+    I: Iterator<Item = Result<CommentedSentence, Error>>,
+{
+    type Item = Result<Vec<CommentedSentence>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let first = match self.next.take() {
+            Some(sentence) => sentence,
+            None => match self.sentences.next()? {
+                Ok(sentence) => sentence,
+                Err(err) => return Some(Err(err)),
+            },
+        };
+
+        let mut unit = vec![first];
+
+        for sentence in &mut self.sentences {
+            let sentence = match sentence {
+                Ok(sentence) => sentence,
+                Err(err) => return Some(Err(err)),
+            };
+
+            if self.unit.starts_unit(&sentence) {
+                self.next = Some(sentence);
+                break;
+            }
+
+            unit.push(sentence);
+        }
+
+        Some(Ok(unit))
+    }
+}